@@ -0,0 +1,58 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use crate::matcher::Matcher;
+
+// size of the read buffer, the first chunk of a file is also checked for binary data
+const READ_CAPACITY: usize = 8 * (1 << 10); // 8 KB
+
+/// A line of a file that contains the search pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMatch {
+    /// Line number, starting at 1
+    pub number: u64,
+    /// The line without its line ending
+    pub line: String,
+}
+
+// all lines of the file matching the pattern
+// returns None for binary files, that is files with a NUL byte in the first chunk
+pub(crate) fn search_file(path: &Path, matcher: &Matcher) -> io::Result<Option<Vec<LineMatch>>> {
+    let mut reader = BufReader::with_capacity(READ_CAPACITY, File::open(path)?);
+
+    if reader.fill_buf()?.contains(&0) {
+        return Ok(None);
+    }
+
+    let mut lines = Vec::new();
+    let mut buf = Vec::new();
+    let mut number = 0;
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        number += 1;
+
+        // strip "\n" or "\r\n"
+        if buf.ends_with(b"\n") {
+            buf.pop();
+            if buf.ends_with(b"\r") {
+                buf.pop();
+            }
+        }
+
+        let line = String::from_utf8_lossy(&buf);
+        if matcher.is_match(&line) {
+            lines.push(LineMatch {
+                number,
+                line: line.into_owned(),
+            });
+        }
+    }
+
+    Ok(Some(lines))
+}
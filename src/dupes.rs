@@ -0,0 +1,291 @@
+use rayon::prelude::*;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+// bytes from the start of a file compared before reading the whole file
+const PARTIAL: u64 = 4 * (1 << 10); // 4 KB
+
+// files with the same content
+#[derive(Debug)]
+pub struct DupeSet {
+    pub size: u64,
+    // sorted, at least two
+    pub paths: Vec<PathBuf>,
+}
+
+impl DupeSet {
+    // everything but one copy
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Dupes {
+    // most wasted bytes first
+    pub sets: Vec<DupeSet>,
+    // files sharing their size with another file, only these are read
+    pub hashed: u64,
+    // files that couldn't be read
+    pub errors: u64,
+}
+
+// files of the same size, then of the same first bytes, then of the same content
+// empty files are never duplicates, hard links to the same file are only counted once
+pub fn find(files: Vec<(PathBuf, u64)>) -> Dupes {
+    let mut by_size = BTreeMap::<u64, Vec<PathBuf>>::new();
+    for (path, size) in files {
+        if size > 0 {
+            by_size.entry(size).or_default().push(path);
+        }
+    }
+
+    let candidates = by_size
+        .into_iter()
+        .flat_map(|(size, paths)| {
+            let paths = distinct_files(paths);
+            let paths = if paths.len() > 1 { paths } else { Vec::new() };
+            paths.into_iter().map(move |path| (size, path))
+        })
+        .collect::<Vec<_>>();
+
+    let mut dupes = Dupes {
+        hashed: candidates.len() as u64,
+        ..Default::default()
+    };
+
+    // small files are read completely already
+    let (complete, partial): (Vec<_>, Vec<_>) = group(candidates, Some(PARTIAL), &mut dupes)
+        .into_iter()
+        .partition(|(size, _)| *size <= PARTIAL);
+
+    let rest = partial
+        .into_iter()
+        .flat_map(|(size, paths)| paths.into_iter().map(move |path| (size, path)))
+        .collect::<Vec<_>>();
+    let complete = complete.into_iter().chain(group(rest, None, &mut dupes));
+
+    dupes.sets = complete
+        .map(|(size, mut paths)| {
+            paths.sort();
+            DupeSet { size, paths }
+        })
+        .collect();
+    dupes.sets.sort_by(|a, b| {
+        b.wasted()
+            .cmp(&a.wasted())
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+
+    dupes
+}
+
+// files of the same size with the same hash of the first `limit` bytes, only groups of at least two
+fn group(
+    files: Vec<(u64, PathBuf)>,
+    limit: Option<u64>,
+    dupes: &mut Dupes,
+) -> Vec<(u64, Vec<PathBuf>)> {
+    let hashed = files
+        .into_par_iter()
+        .map(|(size, path)| (size, hash(&path, limit).ok(), path))
+        .collect::<Vec<_>>();
+
+    let mut groups = HashMap::<(u64, blake3::Hash), Vec<PathBuf>>::new();
+    for (size, hash, path) in hashed {
+        match hash {
+            Some(hash) => groups.entry((size, hash)).or_default().push(path),
+            None => dupes.errors += 1,
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|((size, _), paths)| (size, paths))
+        .collect()
+}
+
+// everything without a limit
+fn hash(path: &Path, limit: Option<u64>) -> io::Result<blake3::Hash> {
+    let mut file = File::open(path)?.take(limit.unwrap_or(u64::MAX));
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(hasher.finalize())
+}
+
+// drop further hard links to a file, they don't take up space
+#[cfg(unix)]
+fn distinct_files(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    use std::{collections::HashSet, os::unix::fs::MetadataExt};
+
+    let mut seen = HashSet::new();
+    paths
+        .into_iter()
+        .filter(|path| match path.metadata() {
+            Ok(metadata) => seen.insert((metadata.dev(), metadata.ino())),
+            Err(_) => true,
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+fn distinct_files(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    use std::fs;
+
+    // every file below the directory with its size, like the search collects them
+    fn files(dir: &TempDir) -> Vec<(PathBuf, u64)> {
+        let mut files = walkdir::WalkDir::new(dir.path())
+            .into_iter()
+            .flatten()
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| (entry.path().to_path_buf(), entry.metadata().unwrap().len()))
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    fn paths(dir: &TempDir, set: &DupeSet) -> Vec<PathBuf> {
+        set.paths
+            .iter()
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect()
+    }
+
+    #[test]
+    fn small_files() {
+        let dir = TempDir::new("dupes-small");
+        dir.write("a", "hello");
+        dir.write("sub/b", "hello");
+        dir.write("c", "hello");
+        dir.write("d", "world");
+        dir.write("e", "other size");
+
+        let dupes = find(files(&dir));
+        assert_eq!(dupes.hashed, 4);
+        assert_eq!(dupes.errors, 0);
+        assert_eq!(dupes.sets.len(), 1);
+        assert_eq!(dupes.sets[0].size, 5);
+        assert_eq!(dupes.sets[0].wasted(), 10);
+        assert_eq!(
+            paths(&dir, &dupes.sets[0]),
+            [Path::new("a"), Path::new("c"), Path::new("sub/b")]
+        );
+    }
+
+    #[test]
+    fn differ_after_the_first_bytes() {
+        let dir = TempDir::new("dupes-partial");
+        let content = vec![b'x'; PARTIAL as usize + 100];
+        let mut other = content.clone();
+        *other.last_mut().unwrap() = b'y';
+        dir.write("a", &content);
+        dir.write("b", &other);
+        dir.write("c", &content);
+
+        let dupes = find(files(&dir));
+        assert_eq!(dupes.hashed, 3);
+        assert_eq!(dupes.sets.len(), 1);
+        assert_eq!(
+            paths(&dir, &dupes.sets[0]),
+            [Path::new("a"), Path::new("c")]
+        );
+
+        fs::remove_file(dir.join("c")).unwrap();
+        assert!(find(files(&dir)).sets.is_empty());
+    }
+
+    #[test]
+    fn empty_files() {
+        let dir = TempDir::new("dupes-empty");
+        dir.create(&["a", "b", "sub/c"]);
+
+        let dupes = find(files(&dir));
+        assert_eq!(dupes.hashed, 0);
+        assert!(dupes.sets.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_links() {
+        let dir = TempDir::new("dupes-links");
+        dir.write("a", "hello");
+        fs::hard_link(dir.join("a"), dir.join("link")).unwrap();
+
+        // a file and a hard link to it are the same file
+        let dupes = find(files(&dir));
+        assert_eq!(dupes.hashed, 0);
+        assert!(dupes.sets.is_empty());
+
+        // only one of them counts next to a copy
+        dir.write("copy", "hello");
+        let dupes = find(files(&dir));
+        assert_eq!(dupes.hashed, 2);
+        assert_eq!(dupes.sets.len(), 1);
+        assert_eq!(
+            paths(&dir, &dupes.sets[0]),
+            [Path::new("a"), Path::new("copy")]
+        );
+    }
+
+    #[test]
+    fn unreadable_files() {
+        let dir = TempDir::new("dupes-errors");
+        dir.write("a", "hello");
+        dir.write("b", "hello");
+        let mut files = files(&dir);
+        // e.g. removed after the search found it
+        files.push((dir.join("gone"), 5));
+
+        let dupes = find(files);
+        assert_eq!(dupes.hashed, 3);
+        assert_eq!(dupes.errors, 1);
+        assert_eq!(dupes.sets.len(), 1);
+        assert_eq!(
+            paths(&dir, &dupes.sets[0]),
+            [Path::new("a"), Path::new("b")]
+        );
+    }
+
+    #[test]
+    fn most_wasted_first() {
+        let dir = TempDir::new("dupes-order");
+        // 3 x 10 bytes waste 20, 2 x 100 bytes waste 100, 2 x 20 bytes waste 20
+        for name in ["ten1", "ten2", "ten3"] {
+            dir.write(name, [b'1'; 10]);
+        }
+        for name in ["hundred1", "hundred2"] {
+            dir.write(name, [b'2'; 100]);
+        }
+        for name in ["twenty1", "twenty2"] {
+            dir.write(name, [b'3'; 20]);
+        }
+
+        let dupes = find(files(&dir));
+        let order = dupes
+            .sets
+            .iter()
+            .map(|set| (set.size, set.wasted()))
+            .collect::<Vec<_>>();
+        // a tie goes to the paths
+        assert_eq!(order, [(100, 100), (10, 20), (20, 20)]);
+        assert_eq!(
+            paths(&dir, &dupes.sets[1]),
+            [Path::new("ten1"), Path::new("ten2"), Path::new("ten3")]
+        );
+    }
+}
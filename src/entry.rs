@@ -0,0 +1,110 @@
+use walkdir::DirEntry;
+
+use std::{
+    ffi::OsStr,
+    fs::{self, FileType},
+    io,
+    path::{Path, PathBuf},
+};
+
+// what the filters need to know about an entry
+// implemented for the entries of the sequential and the parallel walker
+pub(crate) trait Entry {
+    fn path(&self) -> &Path;
+    fn into_path(self) -> PathBuf;
+    fn file_name(&self) -> &OsStr {
+        file_name(self.path())
+    }
+    fn file_type(&self) -> FileType;
+    fn depth(&self) -> usize;
+    // only follows symlinks that were followed during the walk
+    fn metadata(&self) -> io::Result<fs::Metadata>;
+}
+
+impl Entry for DirEntry {
+    fn path(&self) -> &Path {
+        self.path()
+    }
+
+    fn into_path(self) -> PathBuf {
+        self.into_path()
+    }
+
+    fn file_name(&self) -> &OsStr {
+        self.file_name()
+    }
+
+    fn file_type(&self) -> FileType {
+        match followed_root(self) {
+            Some(metadata) => metadata.file_type(),
+            None => self.file_type(),
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.depth()
+    }
+
+    fn metadata(&self) -> io::Result<fs::Metadata> {
+        match followed_root(self) {
+            Some(metadata) => Ok(metadata),
+            None => self.metadata().map_err(io::Error::from),
+        }
+    }
+}
+
+// walkdir walks into a symlinked root but reports the link itself
+// use the target like the parallel walker does, unless the link is broken
+fn followed_root(entry: &DirEntry) -> Option<fs::Metadata> {
+    if entry.depth() == 0 && entry.path_is_symlink() {
+        fs::metadata(entry.path()).ok()
+    } else {
+        None
+    }
+}
+
+// the root can end in "..", fall back to the whole path like walkdir
+pub(crate) fn file_name(path: &Path) -> &OsStr {
+    path.file_name().unwrap_or(path.as_os_str())
+}
+
+// an entry read from disk by its path, e.g. one from the index
+#[derive(Debug)]
+pub(crate) struct PathEntry {
+    path: PathBuf,
+    depth: usize,
+    metadata: fs::Metadata,
+}
+
+impl PathEntry {
+    pub(crate) fn new(path: PathBuf, depth: usize, metadata: fs::Metadata) -> Self {
+        Self {
+            path,
+            depth,
+            metadata,
+        }
+    }
+}
+
+impl Entry for PathEntry {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    fn file_type(&self) -> FileType {
+        self.metadata.file_type()
+    }
+
+    fn depth(&self) -> usize {
+        self.depth
+    }
+
+    // symlinks are never followed
+    fn metadata(&self) -> io::Result<fs::Metadata> {
+        Ok(self.metadata.clone())
+    }
+}
@@ -0,0 +1,390 @@
+use log::error;
+
+use std::{
+    ffi::{OsStr, OsString},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+    sync::{mpsc, Arc, Mutex},
+    thread::{self, JoinHandle},
+};
+
+// bytes for the arguments of a single batch command, well below ARG_MAX
+// the environment counts towards the limit as well
+#[cfg(windows)]
+const BATCH_LIMIT: usize = 30 * (1 << 10); // 30 KB, the limit of a command line is 32 KB
+#[cfg(not(windows))]
+const BATCH_LIMIT: usize = 128 * (1 << 10); // 128 KB
+
+// path, parent, basename and basename without extension
+const PLACEHOLDERS: [&str; 4] = ["{}", "{//}", "{/}", "{/.}"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecMode {
+    // run the command once per search result
+    PerHit,
+    // run the command once with all search results
+    Batch,
+}
+
+// command template given via --exec or --exec-batch
+#[derive(Debug, Clone)]
+pub struct Exec {
+    program: String,
+    args: Vec<String>,
+    mode: ExecMode,
+}
+
+impl Exec {
+    // the path is appended if the command has no placeholder
+    pub fn new(mut command: Vec<String>, mode: ExecMode) -> Self {
+        if !command.iter().any(|arg| has_placeholder(arg)) {
+            command.push(PLACEHOLDERS[0].to_string());
+        }
+
+        let program = command.remove(0);
+        Self {
+            program,
+            args: command,
+            mode,
+        }
+    }
+
+    pub fn mode(&self) -> ExecMode {
+        self.mode
+    }
+
+    fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.stdin(Stdio::null());
+        cmd
+    }
+
+    fn run(&self, path: &Path) -> io::Result<Output> {
+        self.command()
+            .args(self.args.iter().map(|arg| expand(arg, path)))
+            .output()
+    }
+
+    // arguments with a placeholder are repeated for every path
+    fn run_batch(&self, paths: &[PathBuf]) -> io::Result<bool> {
+        let mut cmd = self.command();
+        for arg in &self.args {
+            if has_placeholder(arg) {
+                cmd.args(paths.iter().map(|path| expand(arg, path)));
+            } else {
+                cmd.arg(arg);
+            }
+        }
+
+        Ok(cmd.status()?.success())
+    }
+
+    // size of the arguments of a batch command for a single path
+    fn batch_len(&self, path: &Path) -> usize {
+        self.args
+            .iter()
+            .filter(|arg| has_placeholder(arg))
+            .map(|arg| expand(arg, path).len() + 1)
+            .sum()
+    }
+}
+
+// number of commands run and how many of them failed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecStatus {
+    pub commands: u64,
+    pub failed: u64,
+}
+
+impl ExecStatus {
+    fn record(&mut self, success: bool) {
+        self.commands += 1;
+        if !success {
+            self.failed += 1;
+        }
+    }
+}
+
+// runs the commands for the search results
+// per hit commands run in parallel, the output of every command is printed at once
+pub enum Runner {
+    Parallel {
+        sender: mpsc::Sender<PathBuf>,
+        workers: Vec<JoinHandle<()>>,
+        status: Arc<Mutex<ExecStatus>>,
+    },
+    Batch {
+        exec: Exec,
+        paths: Vec<PathBuf>,
+    },
+}
+
+impl Runner {
+    pub fn start(exec: &Exec) -> Self {
+        match exec.mode() {
+            ExecMode::PerHit => {
+                let (sender, receiver) = mpsc::channel::<PathBuf>();
+                let receiver = Arc::new(Mutex::new(receiver));
+                let status = Arc::new(Mutex::new(ExecStatus::default()));
+                let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+                let workers = (0..threads)
+                    .map(|_| {
+                        let exec = exec.clone();
+                        let receiver = Arc::clone(&receiver);
+                        let status = Arc::clone(&status);
+                        thread::spawn(move || loop {
+                            // the lock is released before the command runs
+                            let path = match receiver.lock() {
+                                Ok(receiver) => receiver.recv(),
+                                Err(_) => break,
+                            };
+                            let Ok(path) = path else { break };
+
+                            let success = run_and_print(&exec, &path);
+                            if let Ok(mut status) = status.lock() {
+                                status.record(success);
+                            }
+                        })
+                    })
+                    .collect();
+
+                Runner::Parallel {
+                    sender,
+                    workers,
+                    status,
+                }
+            }
+            ExecMode::Batch => Runner::Batch {
+                exec: exec.clone(),
+                paths: Vec::new(),
+            },
+        }
+    }
+
+    pub fn push(&mut self, path: PathBuf) {
+        match self {
+            Runner::Parallel { sender, .. } => {
+                // workers only stop once the sender is dropped
+                let _ = sender.send(path);
+            }
+            Runner::Batch { paths, .. } => paths.push(path),
+        }
+    }
+
+    // wait for all commands, batch commands only start now
+    pub fn finish(self) -> ExecStatus {
+        match self {
+            Runner::Parallel {
+                sender,
+                workers,
+                status,
+            } => {
+                drop(sender);
+                for worker in workers {
+                    let _ = worker.join();
+                }
+
+                let status = status.lock().map(|status| *status);
+                status.unwrap_or_default()
+            }
+            Runner::Batch { exec, paths } => {
+                let mut status = ExecStatus::default();
+                for chunk in chunks(&exec, &paths) {
+                    let success = exec.run_batch(chunk).unwrap_or_else(|err| {
+                        error!("Unable to run \'{}\': {err}", exec.program);
+                        false
+                    });
+                    status.record(success);
+                }
+
+                status
+            }
+        }
+    }
+}
+
+fn run_and_print(exec: &Exec, path: &Path) -> bool {
+    match exec.run(path) {
+        Ok(output) => {
+            // keep the output of parallel commands from interleaving
+            let _ = io::stdout().lock().write_all(&output.stdout);
+            let _ = io::stderr().lock().write_all(&output.stderr);
+            output.status.success()
+        }
+        Err(err) => {
+            error!("Unable to run \'{}\': {err}", exec.program);
+            false
+        }
+    }
+}
+
+// split the paths so that no batch command exceeds the argument limit
+fn chunks<'a>(exec: &Exec, paths: &'a [PathBuf]) -> Vec<&'a [PathBuf]> {
+    let fixed = exec.program.len() + exec.args.iter().map(|arg| arg.len() + 1).sum::<usize>();
+
+    let mut chunks = Vec::new();
+    let (mut start, mut len) = (0, fixed);
+    for (i, path) in paths.iter().enumerate() {
+        let path_len = exec.batch_len(path);
+        if i > start && len + path_len > BATCH_LIMIT {
+            chunks.push(&paths[start..i]);
+            (start, len) = (i, fixed);
+        }
+        len += path_len;
+    }
+
+    if start < paths.len() {
+        chunks.push(&paths[start..]);
+    }
+
+    chunks
+}
+
+fn has_placeholder(arg: &str) -> bool {
+    PLACEHOLDERS
+        .iter()
+        .any(|placeholder| arg.contains(placeholder))
+}
+
+// replace every placeholder in the argument, longest ones are checked first
+fn expand(arg: &str, path: &Path) -> OsString {
+    let mut expanded = OsString::new();
+    let mut rest = arg;
+    while let Some(pos) = rest.find('{') {
+        expanded.push(&rest[..pos]);
+        rest = &rest[pos..];
+
+        let replacement = if rest.starts_with("{//}") {
+            Some(("{//}", parent(path)))
+        } else if rest.starts_with("{/.}") {
+            Some(("{/.}", path.file_stem().unwrap_or(path.as_os_str())))
+        } else if rest.starts_with("{/}") {
+            Some(("{/}", path.file_name().unwrap_or(path.as_os_str())))
+        } else if rest.starts_with("{}") {
+            Some(("{}", path.as_os_str()))
+        } else {
+            None
+        };
+
+        match replacement {
+            Some((placeholder, value)) => {
+                expanded.push(value);
+                rest = &rest[placeholder.len()..];
+            }
+            None => {
+                expanded.push("{");
+                rest = &rest[1..];
+            }
+        }
+    }
+    expanded.push(rest);
+
+    expanded
+}
+
+fn parent(path: &Path) -> &OsStr {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.as_os_str(),
+        _ => OsStr::new("."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expanded(arg: &str, path: &str) -> String {
+        expand(arg, Path::new(path)).into_string().unwrap()
+    }
+
+    // bytes of the arguments of a batch command for the chunk
+    fn chunk_len(exec: &Exec, chunk: &[PathBuf]) -> usize {
+        exec.program.len()
+            + exec.args.iter().map(|arg| arg.len() + 1).sum::<usize>()
+            + chunk.iter().map(|path| exec.batch_len(path)).sum::<usize>()
+    }
+
+    #[test]
+    fn placeholders() {
+        assert_eq!(expanded("{}", "dir/sub/file.tar.gz"), "dir/sub/file.tar.gz");
+        assert_eq!(expanded("{//}", "dir/sub/file.tar.gz"), "dir/sub");
+        assert_eq!(expanded("{/}", "dir/sub/file.tar.gz"), "file.tar.gz");
+        assert_eq!(expanded("{/.}", "dir/sub/file.tar.gz"), "file.tar");
+        assert_eq!(expanded("{/.}", "dir/.hidden"), ".hidden");
+        assert_eq!(expanded("{//}", "/file"), "/");
+        assert_eq!(
+            expanded("--out={//}/{/.}.bak", "dir/file.txt"),
+            "--out=dir/file.bak"
+        );
+        assert_eq!(expanded("{}{}", "a"), "aa");
+        assert_eq!(expanded("no placeholder", "a"), "no placeholder");
+    }
+
+    #[test]
+    fn no_parent() {
+        assert_eq!(expanded("{//}", "file.txt"), ".");
+        assert_eq!(expanded("{/}", "file.txt"), "file.txt");
+        assert_eq!(expanded("{/.}", "file.txt"), "file");
+    }
+
+    #[test]
+    fn unknown_braces() {
+        assert_eq!(expanded("{x}", "a"), "{x}");
+        assert_eq!(expanded("{", "a"), "{");
+        assert_eq!(expanded("}", "a"), "}");
+        assert_eq!(expanded("{/", "a"), "{/");
+        assert_eq!(expanded("{{}}", "a"), "{a}");
+        assert_eq!(expanded("{{/}", "dir/a"), "{a");
+        assert_eq!(expanded("ä{//x}ö", "a"), "ä{//x}ö");
+    }
+
+    #[test]
+    fn path_appended_without_placeholder() {
+        let exec = Exec::new(vec!["echo".into(), "-n".into()], ExecMode::PerHit);
+        assert_eq!(exec.program, "echo");
+        assert_eq!(exec.args, ["-n", "{}"]);
+
+        let exec = Exec::new(
+            vec!["mv".into(), "{}".into(), "{//}/old".into()],
+            ExecMode::Batch,
+        );
+        assert_eq!(exec.args, ["{}", "{//}/old"]);
+    }
+
+    #[test]
+    fn chunks_below_the_limit() {
+        let exec = Exec::new(vec!["rm".into(), "-f".into()], ExecMode::Batch);
+        let paths = (0..1000)
+            .map(|i| PathBuf::from(format!("{}/{i}", "x".repeat(500))))
+            .collect::<Vec<_>>();
+
+        let chunks = chunks(&exec, &paths);
+        assert!(chunks.len() > 1);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk_len(&exec, chunk) <= BATCH_LIMIT));
+        assert_eq!(chunks.concat(), paths);
+    }
+
+    #[test]
+    fn oversized_path() {
+        let exec = Exec::new(vec!["rm".into()], ExecMode::Batch);
+        let paths = vec![
+            PathBuf::from("a"),
+            PathBuf::from("x".repeat(BATCH_LIMIT + 10)),
+            PathBuf::from("b"),
+            PathBuf::from("c"),
+        ];
+
+        let chunks = chunks(&exec, &paths);
+        assert_eq!(chunks, [&paths[..1], &paths[1..2], &paths[2..]]);
+    }
+
+    #[test]
+    fn no_chunks_without_paths() {
+        let exec = Exec::new(vec!["rm".into()], ExecMode::Batch);
+        assert!(chunks(&exec, &[]).is_empty());
+    }
+}
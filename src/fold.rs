@@ -0,0 +1,120 @@
+use std::ops::Range;
+
+// case folding, so "STRASSE", "Straße" and "straße" compare equal
+// lowercasing covers almost everything, except the characters listed in fold_special
+pub(crate) fn fold(s: &str) -> String {
+    let mut folded = String::with_capacity(s.len());
+    for c in s.chars() {
+        push_folded(c, &mut folded);
+    }
+
+    folded
+}
+
+// folded string and the byte range of the original character every folded byte comes from
+pub(crate) fn fold_with_offsets(s: &str) -> (String, Vec<Range<usize>>) {
+    let mut folded = String::with_capacity(s.len());
+    let mut origins = Vec::with_capacity(s.len());
+    for (start, c) in s.char_indices() {
+        let before = folded.len();
+        push_folded(c, &mut folded);
+        let origin = start..start + c.len_utf8();
+        origins.extend((before..folded.len()).map(|_| origin.clone()));
+    }
+
+    (folded, origins)
+}
+
+fn push_folded(c: char, folded: &mut String) {
+    match fold_special(c) {
+        Some(special) => folded.push_str(special),
+        None => folded.extend(c.to_lowercase()),
+    }
+}
+
+// full case folding where it differs from lowercasing, see CaseFolding.txt
+fn fold_special(c: char) -> Option<&'static str> {
+    let folded = match c {
+        'ß' | 'ẞ' => "ss",
+        'ſ' => "s",
+        'ς' => "σ",
+        'ϐ' => "β",
+        'ϑ' => "θ",
+        'ϕ' => "φ",
+        'ϖ' => "π",
+        'ϰ' => "κ",
+        'ϱ' => "ρ",
+        'ϵ' => "ε",
+        '\u{345}' | '\u{1fbe}' => "ι",
+        'ẛ' => "ṡ",
+        'ﬀ' => "ff",
+        'ﬁ' => "fi",
+        'ﬂ' => "fl",
+        'ﬃ' => "ffi",
+        'ﬄ' => "ffl",
+        'ﬅ' | 'ﬆ' => "st",
+        _ => return None,
+    };
+
+    Some(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds() {
+        assert_eq!(fold("STRASSE"), "strasse");
+        assert_eq!(fold("Straße"), "strasse");
+        assert_eq!(fold("STRAẞE"), "strasse");
+        assert_eq!(fold("ПРИВЕТ"), "привет");
+        assert_eq!(fold("ΣΊΣΥΦΟΣ"), fold("σίσυφος"));
+        assert_eq!(fold("ﬁle"), "file");
+        assert_eq!(fold(""), "");
+    }
+
+    #[test]
+    fn offsets_of_ascii() {
+        let (folded, origins) = fold_with_offsets("AbC");
+        assert_eq!(folded, "abc");
+        assert_eq!(origins, [0..1, 1..2, 2..3]);
+    }
+
+    #[test]
+    fn offsets_of_multibyte() {
+        // "Ä" is two bytes, folded to the two bytes of "ä"
+        let (folded, origins) = fold_with_offsets("xÄy");
+        assert_eq!(folded, "xäy");
+        assert_eq!(origins, [0..1, 1..3, 1..3, 3..4]);
+    }
+
+    #[test]
+    fn offsets_of_expansions() {
+        // "ß" is two bytes, folded to "ss", "ﬃ" is three bytes, folded to "ffi"
+        let (folded, origins) = fold_with_offsets("aßﬃb");
+        assert_eq!(folded, "assffib");
+        assert_eq!(origins, [0..1, 1..3, 1..3, 3..6, 3..6, 3..6, 6..7]);
+    }
+
+    #[test]
+    fn offsets_of_shrinking() {
+        // the kelvin sign is three bytes, folded to the single byte "k"
+        let (folded, origins) = fold_with_offsets("\u{212a}m");
+        assert_eq!(folded, "km");
+        assert_eq!(origins, [0..3, 3..4]);
+    }
+
+    #[test]
+    fn offsets_cover_every_byte() {
+        for s in ["", "Straße", "ΣΊΣΥΦΟΣ", "ﬃx\u{130}", "日本語.TXT"] {
+            let (folded, origins) = fold_with_offsets(s);
+            assert_eq!(origins.len(), folded.len(), "{:?}", s);
+            assert!(
+                origins.iter().all(|origin| s.get(origin.clone()).is_some()),
+                "{:?}",
+                s
+            );
+        }
+    }
+}
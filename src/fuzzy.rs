@@ -0,0 +1,215 @@
+use std::ops::Range;
+
+// scores for a matched pattern character
+const SCORE_MATCH: i64 = 16;
+// extra score if the previous pattern character matched the previous name character
+const BONUS_CONSECUTIVE: i64 = 24;
+// extra score at the start of the name
+const BONUS_START: i64 = 32;
+// extra score after a separator like '_' or '.'
+const BONUS_BOUNDARY: i64 = 16;
+// extra score for an uppercase character following a lowercase one
+const BONUS_CAMEL: i64 = 14;
+// penalty for every skipped character between two matched characters
+const PENALTY_GAP: i64 = 4;
+// penalty for every unmatched character before the first and after the last match
+// keeps shorter names above longer ones with the same match
+const PENALTY_UNMATCHED: i64 = 1;
+
+const SEPARATORS: [char; 6] = ['/', '\\', '-', '_', '.', ' '];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FuzzyMatch {
+    pub(crate) score: i64,
+    // byte ranges of the matched characters, consecutive characters are merged
+    pub(crate) spans: Vec<Range<usize>>,
+}
+
+// cheap check if the pattern is a subsequence of the name, no scoring
+pub(crate) fn is_subsequence(pattern: &[char], name: &str, case_insensitive: bool) -> bool {
+    let mut pattern = pattern.iter().peekable();
+    for c in name.chars() {
+        match pattern.peek() {
+            Some(p) if chars_eq(**p, c, case_insensitive) => {
+                pattern.next();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+
+    pattern.peek().is_none()
+}
+
+// find the best scoring way to match the pattern as subsequence of the name
+pub(crate) fn fuzzy_match(
+    pattern: &[char],
+    name: &str,
+    case_insensitive: bool,
+) -> Option<FuzzyMatch> {
+    let chars = name.char_indices().collect::<Vec<_>>();
+    let (m, n) = (pattern.len(), chars.len());
+
+    if m == 0 {
+        return Some(FuzzyMatch {
+            score: -(n as i64) * PENALTY_UNMATCHED,
+            spans: Vec::new(),
+        });
+    }
+    if m > n || !is_subsequence(pattern, name, case_insensitive) {
+        return None;
+    }
+
+    let bonus = (0..n)
+        .map(|j| position_bonus(&chars, j))
+        .collect::<Vec<_>>();
+
+    // best[i][j]: best score with pattern[..=i] matched and pattern[i] at name[j]
+    // prev[i][j]: position of pattern[i - 1] for that score
+    let mut best = vec![vec![None::<i64>; n]; m];
+    let mut prev = vec![vec![0usize; n]; m];
+
+    for j in 0..n {
+        if chars_eq(pattern[0], chars[j].1, case_insensitive) {
+            best[0][j] = Some(SCORE_MATCH + bonus[j] - j as i64 * PENALTY_UNMATCHED);
+        }
+    }
+
+    for i in 1..m {
+        // best previous match at least one character before j - 1, already penalized for the gap up to j
+        let mut gapped: Option<(i64, usize)> = None;
+        for j in i..n {
+            if j >= 2 {
+                match (best[i - 1][j - 2], gapped) {
+                    (Some(score), Some((s, _))) if score <= s => {}
+                    (Some(score), _) => gapped = Some((score, j - 2)),
+                    (None, _) => {}
+                }
+            }
+            gapped = gapped.map(|(s, k)| (s - PENALTY_GAP, k));
+
+            if !chars_eq(pattern[i], chars[j].1, case_insensitive) {
+                continue;
+            }
+
+            let consecutive = best[i - 1][j - 1].map(|s| (s + BONUS_CONSECUTIVE, j - 1));
+            let from = match (consecutive, gapped) {
+                (Some(c), Some(g)) => Some(if c.0 >= g.0 { c } else { g }),
+                (c, g) => c.or(g),
+            };
+
+            if let Some((score, k)) = from {
+                best[i][j] = Some(score + SCORE_MATCH + bonus[j]);
+                prev[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = best[m - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (j, s - (n - 1 - j) as i64 * PENALTY_UNMATCHED)))
+        .max_by_key(|&(j, s)| (s, std::cmp::Reverse(j)))?;
+
+    // walk back to collect the matched positions
+    let mut positions = vec![j];
+    for i in (1..m).rev() {
+        j = prev[i][j];
+        positions.push(j);
+    }
+    positions.reverse();
+
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    for j in positions {
+        let (start, c) = chars[j];
+        let end = start + c.len_utf8();
+        match spans.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => spans.push(start..end),
+        }
+    }
+
+    Some(FuzzyMatch { score, spans })
+}
+
+fn position_bonus(chars: &[(usize, char)], j: usize) -> i64 {
+    if j == 0 {
+        return BONUS_START;
+    }
+
+    let (before, current) = (chars[j - 1].1, chars[j].1);
+    if SEPARATORS.contains(&before) {
+        BONUS_BOUNDARY
+    } else if before.is_lowercase() && current.is_uppercase() {
+        BONUS_CAMEL
+    } else {
+        0
+    }
+}
+
+fn chars_eq(a: char, b: char, case_insensitive: bool) -> bool {
+    if case_insensitive {
+        a.to_lowercase().eq(b.to_lowercase())
+    } else {
+        a == b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, name: &str) -> i64 {
+        let pattern = pattern.chars().collect::<Vec<_>>();
+        fuzzy_match(&pattern, name, false)
+            .expect("pattern is a subsequence")
+            .score
+    }
+
+    fn spans(pattern: &str, name: &str) -> Vec<Range<usize>> {
+        let pattern = pattern.chars().collect::<Vec<_>>();
+        fuzzy_match(&pattern, name, true)
+            .expect("pattern is a subsequence")
+            .spans
+    }
+
+    #[test]
+    fn prefix_ranks_above_scattered() {
+        assert!(score("abc", "abcxyz") > score("abc", "xaybzc"));
+        assert!(score("abc", "abcxyz") > score("abc", "xyzabc"));
+    }
+
+    #[test]
+    fn boundary_ranks_above_scattered() {
+        assert!(score("rep", "my_report") > score("rep", "mrxexpxxx"));
+        assert!(score("Rep", "myReport") > score("Rep", "mRxexpxx"));
+    }
+
+    #[test]
+    fn consecutive_ranks_above_gaps() {
+        assert!(score("main", "xmainx") > score("main", "mxaxixn"));
+    }
+
+    #[test]
+    fn shorter_name_ranks_above_longer() {
+        assert!(score("main", "main.rs") > score("main", "main.rs.bak"));
+    }
+
+    #[test]
+    fn no_subsequence() {
+        let pattern = "abc".chars().collect::<Vec<_>>();
+        assert_eq!(fuzzy_match(&pattern, "cab", false), None);
+        assert_eq!(fuzzy_match(&pattern, "ab", false), None);
+        assert_eq!(fuzzy_match(&pattern, "ABC", false), None);
+        assert!(fuzzy_match(&pattern, "ABC", true).is_some());
+    }
+
+    #[test]
+    fn match_positions() {
+        assert_eq!(spans("mrs", "main.rs"), vec![0..1, 5..7]);
+        assert_eq!(spans("main", "domain_main"), vec![7..11]);
+        // byte ranges of multibyte characters
+        assert_eq!(spans("äb", "xäb"), vec![1..4]);
+        assert_eq!(spans("", "main.rs"), Vec::<Range<usize>>::new());
+    }
+}
@@ -0,0 +1,245 @@
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    path::{self, Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+// ignore files in every directory, later ones take precedence
+const IGNORE_FILES: [&str; 3] = [".gitignore", ".ignore", ".sfignore"];
+
+// never worth searching if ignore files are respected
+const GIT_DIR: &str = ".git";
+
+// rules of the ignore files found in the directories of a search
+#[derive(Debug)]
+pub(crate) struct Ignores {
+    search_path: PathBuf,
+    // the rules above the search path are rooted at absolute directories, also for a relative search path
+    absolute_path: PathBuf,
+    // directories above the search path up to the git repository root, nearest first
+    parents: Vec<Gitignore>,
+    // core.excludesFile or $XDG_CONFIG_HOME/git/ignore
+    global: Option<Gitignore>,
+    // rules per directory below the search path, None if it has no ignore files
+    cache: RwLock<HashMap<PathBuf, Option<Arc<Gitignore>>>>,
+}
+
+impl Ignores {
+    pub(crate) fn new(search_path: &Path) -> Self {
+        let (global, err) = Gitignore::global();
+        let global = (err.is_none() && !global.is_empty()).then_some(global);
+
+        let absolute_path =
+            path::absolute(search_path).unwrap_or_else(|_| search_path.to_path_buf());
+
+        Self {
+            search_path: search_path.to_path_buf(),
+            parents: parent_rules(&absolute_path),
+            absolute_path,
+            global,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    // the nearest ignore file with a rule for the path decides
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if is_dir && path.file_name() == Some(OsStr::new(GIT_DIR)) {
+            return true;
+        }
+
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            if let Some(rules) = self.rules(current) {
+                match rules.matched(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+
+            if current == self.search_path {
+                break;
+            }
+            dir = current.parent();
+        }
+
+        if !self.parents.is_empty() {
+            let absolute = match path.strip_prefix(&self.search_path) {
+                Ok(relative) if !relative.as_os_str().is_empty() => {
+                    self.absolute_path.join(relative)
+                }
+                _ => self.absolute_path.clone(),
+            };
+            for rules in &self.parents {
+                match rules.matched(&absolute, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => {}
+                }
+            }
+        }
+
+        if let Some(rules) = &self.global {
+            match rules.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        false
+    }
+
+    fn rules(&self, dir: &Path) -> Option<Arc<Gitignore>> {
+        if let Some(rules) = self.cache.read().ok()?.get(dir) {
+            return rules.clone();
+        }
+
+        let rules = dir_rules(dir).map(Arc::new);
+        if let Ok(mut cache) = self.cache.write() {
+            cache.insert(dir.to_path_buf(), rules.clone());
+        }
+
+        rules
+    }
+}
+
+// rules of all ignore files in a directory
+// broken lines are skipped, like git does
+fn dir_rules(dir: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut found = false;
+    for file in IGNORE_FILES {
+        let path = dir.join(file);
+        if path.is_file() {
+            builder.add(path);
+            found = true;
+        }
+    }
+
+    if !found {
+        return None;
+    }
+
+    builder.build().ok().filter(|rules| !rules.is_empty())
+}
+
+// ignore files of the directories above the search path apply, if they belong to the same git repository
+// the search path has to be absolute, a relative one has no parents to look at
+fn parent_rules(search_path: &Path) -> Vec<Gitignore> {
+    let mut rules = Vec::new();
+    for dir in search_path.ancestors().skip(1) {
+        rules.extend(dir_rules(dir));
+        if dir.join(GIT_DIR).exists() {
+            return rules;
+        }
+    }
+
+    // not inside a git repository
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn ignored(ignores: &Ignores, dir: &TempDir, path: &str) -> bool {
+        match path.strip_suffix('/') {
+            Some(path) => ignores.is_ignored(&dir.join(path), true),
+            None => ignores.is_ignored(&dir.join(path), false),
+        }
+    }
+
+    #[test]
+    fn nested_files() {
+        let dir = TempDir::new("ignores-nested");
+        dir.write(".gitignore", "*.log\nbuild/\n");
+        dir.write("sub/.ignore", "*.tmp\n");
+        dir.write("sub/deeper/.sfignore", "/only-here\n");
+        let ignores = Ignores::new(dir.path());
+
+        assert!(ignored(&ignores, &dir, "a.log"));
+        assert!(ignored(&ignores, &dir, "sub/deeper/a.log"));
+        assert!(ignored(&ignores, &dir, "build/"));
+        assert!(ignored(&ignores, &dir, "sub/build/"));
+        assert!(!ignored(&ignores, &dir, "build"));
+        assert!(!ignored(&ignores, &dir, "a.tmp"));
+        assert!(ignored(&ignores, &dir, "sub/a.tmp"));
+        assert!(ignored(&ignores, &dir, "sub/deeper/a.tmp"));
+        assert!(ignored(&ignores, &dir, "sub/deeper/only-here"));
+        assert!(!ignored(&ignores, &dir, "sub/deeper/more/only-here"));
+        assert!(!ignored(&ignores, &dir, "sub/only-here"));
+        assert!(!ignored(&ignores, &dir, "a.txt"));
+    }
+
+    #[test]
+    fn nearest_rule_wins() {
+        let dir = TempDir::new("ignores-whitelist");
+        dir.write(".gitignore", "*.log\n");
+        dir.write("sub/.gitignore", "!keep.log\n");
+        // later ignore files of a directory take precedence
+        dir.write("other/.gitignore", "*.txt\n");
+        dir.write("other/.sfignore", "!a.txt\n");
+        let ignores = Ignores::new(dir.path());
+
+        assert!(ignored(&ignores, &dir, "keep.log"));
+        assert!(!ignored(&ignores, &dir, "sub/keep.log"));
+        assert!(!ignored(&ignores, &dir, "sub/deeper/keep.log"));
+        assert!(ignored(&ignores, &dir, "sub/other.log"));
+        assert!(!ignored(&ignores, &dir, "other/a.txt"));
+        assert!(ignored(&ignores, &dir, "other/b.txt"));
+    }
+
+    #[test]
+    fn parent_rules_up_to_the_repository() {
+        let dir = TempDir::new("ignores-parents");
+        dir.write(".gitignore", "*.md\n");
+        dir.create(&["repo/.git/"]);
+        dir.write("repo/.gitignore", "*.log\n/sub/anchored\n");
+        dir.write("repo/sub/.gitignore", "!keep.log\n");
+        dir.write("repo/sub/deeper/.gitignore", "*.tmp\n");
+        let ignores = Ignores::new(&dir.join("repo/sub/deeper"));
+
+        // rules of every directory up to the root of the repository
+        assert!(ignored(&ignores, &dir, "repo/sub/deeper/a.log"));
+        assert!(ignored(&ignores, &dir, "repo/sub/deeper/a.tmp"));
+        assert!(!ignored(&ignores, &dir, "repo/sub/deeper/keep.log"));
+        // but not above it
+        assert!(!ignored(&ignores, &dir, "repo/sub/deeper/a.md"));
+
+        // the anchored rule is rooted at the repository
+        let ignores = Ignores::new(&dir.join("repo/sub"));
+        assert!(ignored(&ignores, &dir, "repo/sub/anchored"));
+        assert!(!ignored(&ignores, &dir, "repo/sub/deeper/anchored"));
+    }
+
+    #[test]
+    fn no_parent_rules_outside_of_a_repository() {
+        let dir = TempDir::new("ignores-no-repo");
+        dir.write(".gitignore", "*.log\n");
+        dir.create(&["sub/"]);
+        let ignores = Ignores::new(&dir.join("sub"));
+
+        assert!(!ignored(&ignores, &dir, "sub/a.log"));
+    }
+
+    #[test]
+    fn git_dirs() {
+        let dir = TempDir::new("ignores-git");
+        dir.create(&[".git/", "sub/"]);
+        let ignores = Ignores::new(dir.path());
+
+        assert!(ignored(&ignores, &dir, ".git/"));
+        assert!(ignored(&ignores, &dir, "sub/.git/"));
+        // e.g. the .git file of a submodule
+        assert!(!ignored(&ignores, &dir, "sub/.git"));
+        assert!(!ignored(&ignores, &dir, "sub/.github/"));
+    }
+}
@@ -0,0 +1,907 @@
+use std::{
+    collections::HashMap,
+    error,
+    ffi::{OsStr, OsString},
+    fmt,
+    fs::{self, FileType},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{entry, ignores::Ignores, FileKind, HiddenMode};
+
+// start of every index file, the last byte is the version of the format
+const MAGIC: &[u8; 8] = b"SFINDEX\x01";
+
+// bits of the flags of a record, the lowest two hold the kind
+const KIND_MASK: u8 = 0b11;
+const HIDDEN: u8 = 0b100; // hidden attribute on windows
+const IGNORED: u8 = 0b1000; // listed in an ignore file
+
+// record without a directory below it
+const NO_DIR: u32 = u32::MAX;
+
+// timestamps this close to the scan can't be trusted on filesystems with a coarse resolution
+const MTIME_SLACK: Duration = Duration::from_secs(2);
+
+/// Names of all entries below some directories, stored on disk to search them without walking
+///
+/// Build it once with [`Index::build`], refresh it with [`Index::update`]
+/// and search it via [`SearchBuilder::index`](crate::SearchBuilder::index).
+#[derive(Debug, Default)]
+pub struct Index {
+    trees: Vec<Tree>,
+}
+
+// an indexed directory and everything below it
+#[derive(Debug, Default)]
+struct Tree {
+    // canonical
+    path: PathBuf,
+    // the first one is the indexed directory itself
+    dirs: Vec<Dir>,
+    // the records of a directory are next to each other, sorted by name
+    records: Vec<Record>,
+    names: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Dir {
+    // seconds and nanoseconds since the unix epoch, None forces a rescan on the next update
+    mtime: Option<(i64, u32)>,
+    first: u32,
+    len: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Record {
+    name: u32,
+    name_len: u16,
+    flags: u8,
+    // index of the directory in Tree::dirs
+    dir: u32,
+}
+
+// an entry of a directory to index, with its directory in the previous tree
+type Listed<'t> = (OsString, u8, Option<(&'t Tree, u32)>);
+
+/// What [`Index::build`] and [`Index::update`] did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    /// Indexed directories
+    pub dirs: u64,
+    /// Indexed entries, including the directories
+    pub entries: u64,
+    /// Directories whose entries were read again, all of them when building
+    pub rescanned: u64,
+    /// Directories that could not be read, an update keeps their previous entries
+    pub errors: u64,
+}
+
+/// An index file that could not be read
+#[derive(Debug)]
+pub enum IndexError {
+    Io(io::Error),
+    /// Not an index file or written by a different version
+    Invalid(PathBuf),
+}
+
+impl fmt::Display for IndexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexError::Io(err) => err.fmt(f),
+            IndexError::Invalid(path) => write!(
+                f,
+                "{} is no valid index, rebuild it via sf index build",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl error::Error for IndexError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            IndexError::Io(err) => Some(err),
+            IndexError::Invalid(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for IndexError {
+    fn from(err: io::Error) -> Self {
+        IndexError::Io(err)
+    }
+}
+
+impl Index {
+    /// Read an index written by [`Index::save`]
+    pub fn open(path: &Path) -> Result<Self, IndexError> {
+        let bytes = fs::read(path)?;
+        Reader::new(&bytes)
+            .index()
+            .ok_or_else(|| IndexError::Invalid(path.to_path_buf()))
+    }
+
+    /// Write the index to `path`, replacing the previous one at once
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let bytes = self.to_bytes();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    }
+
+    // read back by Reader::index
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        write_u32(&mut bytes, self.trees.len());
+        for tree in &self.trees {
+            tree.write(&mut bytes);
+        }
+
+        bytes
+    }
+
+    /// The indexed directories
+    pub fn roots(&self) -> impl Iterator<Item = &Path> {
+        self.trees.iter().map(|tree| tree.path.as_path())
+    }
+
+    /// Index every entry below `path`
+    ///
+    /// Replaces the indexed directories at or below `path`.
+    /// Hidden and ignored entries are indexed as well and only skipped when searching.
+    pub fn build(&mut self, path: &Path) -> io::Result<IndexStats> {
+        let path = fs::canonicalize(path)?;
+        if !fs::metadata(&path)?.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "only directories can be indexed",
+            ));
+        }
+
+        let mut stats = IndexStats::default();
+        let tree = Tree::scan(path, None, &mut stats);
+        self.trees.retain(|it| !it.path.starts_with(&tree.path));
+        self.trees.push(tree);
+
+        Ok(stats)
+    }
+
+    /// Read the entries of the directories modified since the last build or update again
+    ///
+    /// Unmodified directories are only checked for their modification time.
+    /// Indexed directories that can't be read anymore, e.g. an unmounted drive, are kept as they are.
+    pub fn update(&mut self) -> IndexStats {
+        let mut stats = IndexStats::default();
+        for tree in &mut self.trees {
+            if fs::metadata(&tree.path).is_err() {
+                stats.errors += 1;
+                continue;
+            }
+
+            let old = std::mem::take(tree);
+            *tree = Tree::scan(old.path.clone(), Some(&old), &mut stats);
+        }
+
+        stats
+    }
+
+    // the tree and the record of the deepest indexed directory containing the canonical path
+    fn find(&self, path: &Path) -> Option<(usize, Option<Record>)> {
+        let (index, tree) = self
+            .trees
+            .iter()
+            .enumerate()
+            .filter(|(_, tree)| path.starts_with(&tree.path))
+            .max_by_key(|(_, tree)| tree.path.components().count())?;
+
+        // the indexed directory itself has no record
+        let mut record = None;
+        let mut dir = 0;
+        for component in path.strip_prefix(&tree.path).ok()?.components() {
+            if dir == NO_DIR {
+                return None;
+            }
+            let name = component.as_os_str();
+            let found = *tree
+                .records_of(dir)
+                .iter()
+                .find(|record| tree.name(record) == name)?;
+            record = Some(found);
+            dir = found.dir;
+        }
+
+        Some((index, record))
+    }
+}
+
+impl Tree {
+    // read the directories below path, directories unmodified since the old tree are taken from it
+    fn scan(path: PathBuf, old: Option<&Tree>, stats: &mut IndexStats) -> Self {
+        let mut tree = Tree {
+            path,
+            ..Default::default()
+        };
+        let ignores = Ignores::new(&tree.path);
+        let root = tree.path.clone();
+        tree.scan_dir(&root, old.map(|old| (old, 0)), &ignores, stats);
+
+        tree
+    }
+
+    // returns the index of the new directory
+    fn scan_dir(
+        &mut self,
+        path: &Path,
+        old: Option<(&Tree, u32)>,
+        ignores: &Ignores,
+        stats: &mut IndexStats,
+    ) -> u32 {
+        let index = self.dirs.len() as u32;
+        self.dirs.push(Dir {
+            mtime: None,
+            first: 0,
+            len: 0,
+        });
+        stats.dirs += 1;
+
+        let mut mtime = fs::symlink_metadata(path)
+            .ok()
+            .and_then(|metadata| trusted_mtime(&metadata));
+        let unchanged = mtime.is_some()
+            && old.is_some_and(|(tree, dir)| tree.dirs[dir as usize].mtime == mtime);
+
+        let entries = match old {
+            Some((tree, dir)) if unchanged => tree.listing(dir),
+            _ => match read_dir(path) {
+                Ok(entries) => {
+                    stats.rescanned += 1;
+                    // keep what is known about the directories below
+                    let old_children: HashMap<OsString, (&Tree, u32)> = old
+                        .map(|(tree, dir)| tree.listing(dir))
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(|(name, _, child)| Some((name, child?)))
+                        .collect();
+
+                    entries
+                        .into_iter()
+                        .map(|(name, flags)| {
+                            let old_child = old_children.get(&name).copied();
+                            (name, flags, old_child)
+                        })
+                        .collect()
+                }
+                Err(_) => {
+                    // keep the previous entries, rescan on the next update
+                    stats.errors += 1;
+                    mtime = None;
+                    old.map(|(tree, dir)| tree.listing(dir)).unwrap_or_default()
+                }
+            },
+        };
+        self.dirs[index as usize].mtime = mtime;
+
+        // the records of a directory come first, the directories below after them
+        let first = self.records.len();
+        for (name, flags, _) in &entries {
+            let is_dir = flags & KIND_MASK == kind_bits(FileKind::Dir);
+            // ignore files may have changed even if the directory didn't
+            let flags = if ignores.is_ignored(&path.join(name), is_dir) {
+                flags | IGNORED
+            } else {
+                flags & !IGNORED
+            };
+            let bytes = os_to_bytes(name);
+            self.records.push(Record {
+                name: self.names.len() as u32,
+                name_len: bytes.len() as u16,
+                flags,
+                dir: NO_DIR,
+            });
+            self.names.extend_from_slice(&bytes);
+        }
+        self.dirs[index as usize].first = first as u32;
+        self.dirs[index as usize].len = entries.len() as u32;
+        stats.entries += entries.len() as u64;
+
+        for (i, (name, flags, old_child)) in entries.iter().enumerate() {
+            if flags & KIND_MASK == kind_bits(FileKind::Dir) {
+                let dir = self.scan_dir(&path.join(name), *old_child, ignores, stats);
+                self.records[first + i].dir = dir;
+            }
+        }
+
+        index
+    }
+
+    // name, flags and directory of every record of a directory
+    fn listing(&self, dir: u32) -> Vec<Listed<'_>> {
+        self.records_of(dir)
+            .iter()
+            .map(|record| {
+                let child = (record.dir != NO_DIR).then_some((self, record.dir));
+                (self.name(record).to_owned(), record.flags, child)
+            })
+            .collect()
+    }
+
+    fn records_of(&self, dir: u32) -> &[Record] {
+        let dir = &self.dirs[dir as usize];
+        &self.records[dir.first as usize..(dir.first + dir.len) as usize]
+    }
+
+    fn name(&self, record: &Record) -> &OsStr {
+        let start = record.name as usize;
+        bytes_to_os(&self.names[start..start + record.name_len as usize])
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        let path = os_to_bytes(self.path.as_os_str());
+        write_u32(bytes, path.len());
+        bytes.extend_from_slice(&path);
+
+        write_u32(bytes, self.dirs.len());
+        for dir in &self.dirs {
+            let (secs, nanos) = dir.mtime.unwrap_or((0, u32::MAX));
+            bytes.extend_from_slice(&secs.to_le_bytes());
+            bytes.extend_from_slice(&nanos.to_le_bytes());
+            bytes.extend_from_slice(&dir.first.to_le_bytes());
+            bytes.extend_from_slice(&dir.len.to_le_bytes());
+        }
+
+        write_u32(bytes, self.records.len());
+        for record in &self.records {
+            bytes.push(record.flags);
+            bytes.extend_from_slice(&record.dir.to_le_bytes());
+            let name = &self.names[record.name as usize..][..record.name_len as usize];
+            bytes.extend_from_slice(&record.name_len.to_le_bytes());
+            bytes.extend_from_slice(name);
+        }
+    }
+}
+
+// names and flags of the entries of a directory, sorted by name
+fn read_dir(path: &Path) -> io::Result<Vec<(OsString, u8)>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let flags = match entry.file_type() {
+            Ok(file_type) => kind_bits(kind_of(file_type)) | hidden_bit(&entry),
+            Err(_) => kind_bits(FileKind::Other),
+        };
+        entries.push((entry.file_name(), flags));
+    }
+    entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(entries)
+}
+
+// None if the directory was modified right before it was read
+fn trusted_mtime(metadata: &fs::Metadata) -> Option<(i64, u32)> {
+    let mtime = metadata.modified().ok()?;
+    match SystemTime::now().duration_since(mtime) {
+        Ok(age) if age >= MTIME_SLACK => {}
+        _ => return None,
+    }
+
+    Some(match mtime.duration_since(UNIX_EPOCH) {
+        Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+        Err(err) => {
+            let before = err.duration();
+            (-(before.as_secs() as i64), before.subsec_nanos())
+        }
+    })
+}
+
+fn kind_of(file_type: FileType) -> FileKind {
+    if file_type.is_symlink() {
+        FileKind::Symlink
+    } else if file_type.is_dir() {
+        FileKind::Dir
+    } else if file_type.is_file() {
+        FileKind::File
+    } else {
+        FileKind::Other
+    }
+}
+
+fn kind_bits(kind: FileKind) -> u8 {
+    match kind {
+        FileKind::File => 0,
+        FileKind::Dir => 1,
+        FileKind::Symlink => 2,
+        FileKind::Other => 3,
+    }
+}
+
+fn kind_from_bits(flags: u8) -> FileKind {
+    match flags & KIND_MASK {
+        0 => FileKind::File,
+        1 => FileKind::Dir,
+        2 => FileKind::Symlink,
+        _ => FileKind::Other,
+    }
+}
+
+#[cfg(windows)]
+fn hidden_bit(entry: &fs::DirEntry) -> u8 {
+    use std::os::windows::fs::MetadataExt;
+
+    match entry.metadata() {
+        Ok(metadata) if metadata.file_attributes() & crate::FILE_ATTRIBUTE_HIDDEN > 0 => HIDDEN,
+        _ => 0,
+    }
+}
+
+#[cfg(not(windows))]
+fn hidden_bit(_entry: &fs::DirEntry) -> u8 {
+    0
+}
+
+#[cfg(unix)]
+fn os_to_bytes(s: &OsStr) -> std::borrow::Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+
+    s.as_bytes().into()
+}
+
+#[cfg(not(unix))]
+fn os_to_bytes(s: &OsStr) -> std::borrow::Cow<'_, [u8]> {
+    s.to_string_lossy().into_owned().into_bytes().into()
+}
+
+#[cfg(unix)]
+fn bytes_to_os(bytes: &[u8]) -> &OsStr {
+    use std::os::unix::ffi::OsStrExt;
+
+    OsStr::from_bytes(bytes)
+}
+
+// written from valid UTF-8 by os_to_bytes
+#[cfg(not(unix))]
+fn bytes_to_os(bytes: &[u8]) -> &OsStr {
+    OsStr::new(std::str::from_utf8(bytes).unwrap_or_default())
+}
+
+fn write_u32(bytes: &mut Vec<u8>, n: usize) {
+    bytes.extend_from_slice(&(n as u32).to_le_bytes());
+}
+
+// None as soon as the data doesn't fit the format
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if n > self.bytes.len() {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        Some(i64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn index(mut self) -> Option<Index> {
+        if self.take(MAGIC.len())? != MAGIC {
+            return None;
+        }
+
+        let trees = (0..self.u32()?)
+            .map(|_| self.tree())
+            .collect::<Option<Vec<_>>>()?;
+        if !self.bytes.is_empty() {
+            return None;
+        }
+
+        Some(Index { trees })
+    }
+
+    fn tree(&mut self) -> Option<Tree> {
+        let len = self.u32()? as usize;
+        let path = PathBuf::from(bytes_to_os(self.take(len)?));
+
+        let dir_count = self.u32()? as usize;
+        let mut dirs = Vec::with_capacity(dir_count.min(self.bytes.len()));
+        for _ in 0..dir_count {
+            let secs = self.i64()?;
+            let nanos = self.u32()?;
+            dirs.push(Dir {
+                mtime: (nanos != u32::MAX).then_some((secs, nanos)),
+                first: self.u32()?,
+                len: self.u32()?,
+            });
+        }
+
+        let record_count = self.u32()? as usize;
+        let mut records = Vec::with_capacity(record_count.min(self.bytes.len()));
+        let mut names = Vec::new();
+        for _ in 0..record_count {
+            let flags = self.u8()?;
+            let dir = self.u32()?;
+            let name_len = self.u16()?;
+            records.push(Record {
+                name: names.len() as u32,
+                name_len,
+                flags,
+                dir,
+            });
+            names.extend_from_slice(self.take(name_len as usize)?);
+        }
+
+        // every directory and record has to point into the tree
+        let valid = !dirs.is_empty()
+            && dirs
+                .iter()
+                .all(|dir| dir.first as usize + dir.len as usize <= records.len())
+            && records
+                .iter()
+                .all(|record| record.dir == NO_DIR || (record.dir as usize) < dirs.len());
+        valid.then_some(Tree {
+            path,
+            dirs,
+            records,
+            names,
+        })
+    }
+}
+
+// walks the indexed entries below the search path like the sequential walker does
+pub(crate) struct IndexWalk {
+    index: Arc<Index>,
+    tree: usize,
+    // the search path itself, until it is yielded
+    root: Option<(PathBuf, FileKind, u32)>,
+    stack: Vec<Frame>,
+    max_depth: usize,
+    no_hidden: bool,
+    hidden_mode: HiddenMode,
+    ignore_files: bool,
+}
+
+struct Frame {
+    path: PathBuf,
+    depth: usize,
+    next: u32,
+    end: u32,
+}
+
+impl IndexWalk {
+    // None if the search path is not indexed
+    pub(crate) fn new(
+        index: Arc<Index>,
+        search_path: &Path,
+        max_depth: usize,
+        no_hidden: bool,
+        hidden_mode: HiddenMode,
+        ignore_files: bool,
+    ) -> Option<Self> {
+        let canonical = fs::canonicalize(search_path).ok()?;
+        let (tree, record) = index.find(&canonical)?;
+        let (kind, dir) = match record {
+            Some(record) => (kind_from_bits(record.flags), record.dir),
+            None => (FileKind::Dir, 0),
+        };
+
+        Some(Self {
+            index,
+            tree,
+            root: Some((search_path.to_path_buf(), kind, dir)),
+            stack: Vec::new(),
+            max_depth,
+            no_hidden,
+            hidden_mode,
+            ignore_files,
+        })
+    }
+
+    // the next entry accepted by the given check on its name, type and depth
+    // paths are only put together for accepted entries and directories to enter
+    pub(crate) fn next_accepted(
+        &mut self,
+        mut accept: impl FnMut(&OsStr, FileKind) -> bool,
+    ) -> Option<(PathBuf, usize)> {
+        let tree = &self.index.trees[self.tree];
+
+        if let Some((path, kind, dir)) = self.root.take() {
+            if dir != NO_DIR && self.max_depth > 0 {
+                let dir = tree.dirs[dir as usize];
+                self.stack.push(Frame {
+                    path: path.clone(),
+                    depth: 0,
+                    next: dir.first,
+                    end: dir.first + dir.len,
+                });
+            }
+
+            if accept(entry::file_name(&path), kind) {
+                return Some((path, 0));
+            }
+        }
+
+        loop {
+            let frame = self.stack.last_mut()?;
+            if frame.next == frame.end {
+                self.stack.pop();
+                continue;
+            }
+
+            let record = tree.records[frame.next as usize];
+            frame.next += 1;
+
+            // hidden and ignored directories are not entered
+            let name = tree.name(&record);
+            if (self.no_hidden && is_hidden(name, record.flags, self.hidden_mode))
+                || (self.ignore_files && record.flags & IGNORED != 0)
+            {
+                continue;
+            }
+
+            let depth = frame.depth + 1;
+            let accepted = accept(name, kind_from_bits(record.flags));
+            let enter = record.dir != NO_DIR && depth < self.max_depth;
+            if !accepted && !enter {
+                continue;
+            }
+
+            let path = frame.path.join(name);
+            if enter {
+                let dir = tree.dirs[record.dir as usize];
+                self.stack.push(Frame {
+                    path: path.clone(),
+                    depth,
+                    next: dir.first,
+                    end: dir.first + dir.len,
+                });
+            }
+
+            if accepted {
+                return Some((path, depth));
+            }
+        }
+    }
+}
+
+fn is_hidden(name: &OsStr, flags: u8, hidden_mode: HiddenMode) -> bool {
+    let dotfile = name.to_string_lossy().starts_with('.');
+    let attribute = flags & HIDDEN != 0;
+    match hidden_mode {
+        HiddenMode::Native if cfg!(windows) => attribute,
+        HiddenMode::Native => dotfile,
+        HiddenMode::Both => dotfile || attribute,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    use std::collections::BTreeSet;
+
+    fn tree(name: &str) -> TempDir {
+        let dir = TempDir::new(name);
+        dir.create(&["a.txt", "b/", "b/c.rs", "b/d/e.md", ".hidden/f", "g/"]);
+        dir
+    }
+
+    fn index_walk(
+        index: Index,
+        path: &Path,
+        max_depth: usize,
+        no_hidden: bool,
+    ) -> BTreeSet<(PathBuf, usize)> {
+        let mut walk = IndexWalk::new(
+            Arc::new(index),
+            path,
+            max_depth,
+            no_hidden,
+            HiddenMode::Native,
+            false,
+        )
+        .expect("path is indexed");
+        std::iter::from_fn(|| walk.next_accepted(|_, _| true)).collect()
+    }
+
+    // what the sequential search finds, everything matches the empty pattern
+    fn live_walk(path: &Path, max_depth: usize, no_hidden: bool) -> BTreeSet<(PathBuf, usize)> {
+        crate::SearchBuilder::new("", path)
+            .depth(max_depth.try_into().unwrap_or(u32::MAX))
+            .no_hidden(no_hidden)
+            .threads(1)
+            .build()
+            .unwrap()
+            .map(|hit| {
+                let hit = hit.unwrap();
+                (hit.path, hit.depth)
+            })
+            .collect()
+    }
+
+    fn names(index: &Index) -> BTreeSet<PathBuf> {
+        let root = index.roots().next().unwrap().to_path_buf();
+        index_walk(reopen(index), &root, usize::MAX, false)
+            .into_iter()
+            .map(|(path, _)| path.strip_prefix(&root).unwrap().to_path_buf())
+            .collect()
+    }
+
+    // a copy of the index through its file format
+    fn reopen(index: &Index) -> Index {
+        Reader::new(&index.to_bytes()).index().unwrap()
+    }
+
+    #[test]
+    fn save_and_open() {
+        let dir = tree("index-save");
+        let mut index = Index::default();
+        let stats = index.build(dir.path()).unwrap();
+        assert_eq!(stats.dirs, 5);
+        assert_eq!(stats.entries, 8);
+
+        let file = dir.join("index");
+        index.save(&file).unwrap();
+        let opened = Index::open(&file).unwrap();
+        assert_eq!(opened.roots().collect::<Vec<_>>(), [dir.path()]);
+        assert_eq!(opened.to_bytes(), index.to_bytes());
+        assert_eq!(fs::read(&file).unwrap(), index.to_bytes());
+        assert!(!dir.join("index.tmp").exists());
+    }
+
+    #[test]
+    fn open_missing() {
+        let dir = TempDir::new("index-missing");
+        assert!(matches!(
+            Index::open(&dir.join("index")),
+            Err(IndexError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn reject_invalid() {
+        let dir = tree("index-invalid");
+        let mut index = Index::default();
+        index.build(dir.path()).unwrap();
+        let bytes = index.to_bytes();
+        let file = dir.join("index");
+        let open = |bytes: &[u8]| {
+            fs::write(&file, bytes).unwrap();
+            Index::open(&file)
+        };
+
+        // every truncation
+        for len in 0..bytes.len() {
+            assert!(
+                matches!(open(&bytes[..len]), Err(IndexError::Invalid(_))),
+                "{}",
+                len
+            );
+        }
+        // trailing garbage
+        assert!(matches!(
+            open(&[&bytes[..], b"x"].concat()),
+            Err(IndexError::Invalid(_))
+        ));
+
+        // wrong magic and version
+        let mut wrong = bytes.clone();
+        wrong[0] = b'X';
+        assert!(matches!(open(&wrong), Err(IndexError::Invalid(_))));
+        let mut wrong = bytes.clone();
+        wrong[MAGIC.len() - 1] = 2;
+        assert!(matches!(open(&wrong), Err(IndexError::Invalid(_))));
+
+        // the first record points to a directory that doesn't exist
+        let tree = &index.trees[0];
+        let first_record =
+            MAGIC.len() + 4 + 4 + tree.path.as_os_str().len() + 4 + tree.dirs.len() * 20 + 4;
+        let mut wrong = bytes.clone();
+        wrong[first_record + 1..first_record + 5]
+            .copy_from_slice(&(tree.dirs.len() as u32).to_le_bytes());
+        assert!(matches!(open(&wrong), Err(IndexError::Invalid(_))));
+
+        // a corrupted byte anywhere never panics
+        for i in 0..bytes.len() {
+            let mut wrong = bytes.clone();
+            wrong[i] ^= 0xff;
+            let _ = open(&wrong);
+        }
+
+        assert!(open(&bytes).is_ok());
+    }
+
+    #[test]
+    fn update_picks_up_changes() {
+        let dir = tree("index-update");
+        let mut index = Index::default();
+        index.build(dir.path()).unwrap();
+        let before = names(&index);
+        assert!(before.contains(Path::new("b/c.rs")));
+
+        dir.create(&["b/d/new.txt", "g/h/"]);
+        fs::remove_file(dir.join("b/c.rs")).unwrap();
+        fs::remove_dir_all(dir.join(".hidden")).unwrap();
+
+        // everything was just modified, so every directory is read again
+        let stats = index.update();
+        assert_eq!(stats.errors, 0);
+        assert!(stats.rescanned > 0);
+
+        let after = names(&index);
+        let added = after.difference(&before).collect::<Vec<_>>();
+        let removed = before.difference(&after).collect::<Vec<_>>();
+        assert_eq!(added, [Path::new("b/d/new.txt"), Path::new("g/h")]);
+        assert_eq!(
+            removed,
+            [
+                Path::new(".hidden"),
+                Path::new(".hidden/f"),
+                Path::new("b/c.rs")
+            ]
+        );
+        assert_eq!(after, names(&reopen(&index)));
+    }
+
+    #[test]
+    fn walk_like_the_live_walk() {
+        let dir = tree("index-walk");
+        let mut index = Index::default();
+        index.build(dir.path()).unwrap();
+        // the search path and every entry below it
+        assert_eq!(live_walk(dir.path(), usize::MAX, false).len(), 9);
+
+        for path in [dir.path().to_path_buf(), dir.join("b"), dir.join("b/d")] {
+            for max_depth in [0, 1, usize::MAX] {
+                for no_hidden in [false, true] {
+                    assert_eq!(
+                        index_walk(reopen(&index), &path, max_depth, no_hidden),
+                        live_walk(&path, max_depth, no_hidden),
+                        "{} {} {}",
+                        path.display(),
+                        max_depth,
+                        no_hidden
+                    );
+                }
+            }
+        }
+
+        // not indexed
+        assert!(IndexWalk::new(
+            Arc::new(index),
+            &dir.join("missing"),
+            1,
+            false,
+            HiddenMode::Native,
+            false
+        )
+        .is_none());
+    }
+}
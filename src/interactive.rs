@@ -0,0 +1,371 @@
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+use owo_colors::colored::*;
+use sf::Hit;
+
+use std::{
+    collections::HashSet,
+    io::{self, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+// wait this long for a key while the search is still running
+const TICK: Duration = Duration::from_millis(50);
+
+// sorting is expensive, don't sort the results on every tick
+const SORT_INTERVAL: Duration = Duration::from_millis(500);
+
+// results taken from the search per tick, keeps the ui responsive
+const BATCH: usize = 10_000;
+
+// lines above the results: the query and the status
+const HEADER: u16 = 2;
+
+// opens a path with its default application
+#[cfg(windows)]
+const OPENER: &str = "explorer";
+#[cfg(target_os = "macos")]
+const OPENER: &str = "open";
+#[cfg(not(any(windows, target_os = "macos")))]
+const OPENER: &str = "xdg-open";
+
+// a search result and the byte ranges of its name to highlight
+pub struct Item {
+    pub hit: Hit,
+    pub spans: Vec<Range<usize>>,
+}
+
+// what to do with the picked results
+pub enum Outcome {
+    Print(Vec<PathBuf>),
+    Open(Vec<PathBuf>),
+    Abort,
+}
+
+// raw mode on the alternate screen, restored once dropped, also when panicking
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stderr(), terminal::EnterAlternateScreen)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), terminal::LeaveAlternateScreen, cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Picker {
+    items: Vec<Item>,
+    // indices of the items matching the query, in order
+    shown: Vec<usize>,
+    query: String,
+    // position in shown
+    cursor: usize,
+    // first shown item on the screen
+    offset: usize,
+    // lines available for the results
+    rows: usize,
+    marked: HashSet<PathBuf>,
+    searching: bool,
+}
+
+// results come in while the search is running, the ui is drawn to stderr to keep stdout for the picked paths
+// `order` sorts the results, e.g. by --sort or by the score of fuzzy matches
+pub fn pick(results: mpsc::Receiver<Item>, order: impl Fn(&mut [Item])) -> io::Result<Outcome> {
+    let _screen = Screen::enter()?;
+    let mut out = io::BufWriter::new(io::stderr());
+
+    let mut picker = Picker {
+        items: Vec::new(),
+        shown: Vec::new(),
+        query: String::new(),
+        cursor: 0,
+        offset: 0,
+        rows: 0,
+        marked: HashSet::new(),
+        searching: true,
+    };
+    let mut sorted = Instant::now();
+    let mut unsorted = false;
+
+    loop {
+        // take what the search found since the last tick
+        let before = picker.items.len();
+        while picker.searching && picker.items.len() - before < BATCH {
+            match results.try_recv() {
+                Ok(item) => picker.items.push(item),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => picker.searching = false,
+            }
+        }
+
+        if picker.items.len() > before || (unsorted && !picker.searching) {
+            if !picker.searching || sorted.elapsed() >= SORT_INTERVAL {
+                order(&mut picker.items);
+                picker.filter();
+                sorted = Instant::now();
+                unsorted = false;
+            } else {
+                picker.filter_from(before);
+                unsorted = true;
+            }
+        }
+
+        picker.draw(&mut out)?;
+
+        // keep taking results while waiting for a key
+        if picker.searching && !event::poll(TICK)? {
+            continue;
+        }
+
+        if let Event::Key(key) = event::read()? {
+            if let Some(outcome) = picker.handle(key) {
+                return Ok(outcome);
+            }
+        }
+    }
+}
+
+// open with the default application, doesn't wait for it
+pub fn open(path: &Path) -> io::Result<()> {
+    Command::new(OPENER)
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+impl Picker {
+    // all items again, e.g. once the query or the order changed
+    fn filter(&mut self) {
+        self.shown.clear();
+        self.filter_from(0);
+        self.cursor = self.cursor.min(self.shown.len().saturating_sub(1));
+    }
+
+    // every whitespace separated part of the query has to be in the path
+    // case insensitive, unless the part has an uppercase character
+    fn filter_from(&mut self, start: usize) {
+        let terms = self
+            .query
+            .split_whitespace()
+            .map(|term| {
+                let insensitive = !term.chars().any(char::is_uppercase);
+                let term = if insensitive {
+                    term.to_lowercase()
+                } else {
+                    term.to_string()
+                };
+                (term, insensitive)
+            })
+            .collect::<Vec<_>>();
+
+        for (index, item) in self.items.iter().enumerate().skip(start) {
+            let path = item.hit.path.to_string_lossy();
+            let lowercase = path.to_lowercase();
+            let matched = terms.iter().all(|(term, insensitive)| {
+                if *insensitive {
+                    lowercase.contains(term.as_str())
+                } else {
+                    path.contains(term.as_str())
+                }
+            });
+            if matched {
+                self.shown.push(index);
+            }
+        }
+    }
+
+    // None while picking
+    fn handle(&mut self, key: KeyEvent) -> Option<Outcome> {
+        // windows reports releasing a key as well
+        if key.kind == KeyEventKind::Release {
+            return None;
+        }
+
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let page = self.rows.max(1) as isize;
+        match key.code {
+            KeyCode::Esc => return Some(Outcome::Abort),
+            KeyCode::Char('c' | 'g') if ctrl => return Some(Outcome::Abort),
+            KeyCode::Enter => return Some(Outcome::Print(self.picked())),
+            KeyCode::Char('o') if ctrl => return Some(Outcome::Open(self.picked())),
+            KeyCode::Up => self.move_by(-1),
+            KeyCode::Char('p' | 'k') if ctrl => self.move_by(-1),
+            KeyCode::Down => self.move_by(1),
+            KeyCode::Char('n' | 'j') if ctrl => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-page),
+            KeyCode::PageDown => self.move_by(page),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.shown.len().saturating_sub(1),
+            KeyCode::Tab => {
+                self.toggle();
+                self.move_by(1);
+            }
+            KeyCode::BackTab => {
+                self.toggle();
+                self.move_by(-1);
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.filter();
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.filter();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.filter();
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn move_by(&mut self, by: isize) {
+        let last = self.shown.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(by).min(last);
+    }
+
+    fn current(&self) -> Option<&Item> {
+        self.shown.get(self.cursor).map(|&index| &self.items[index])
+    }
+
+    fn toggle(&mut self) {
+        if let Some(path) = self.current().map(|item| item.hit.path.clone()) {
+            if !self.marked.remove(&path) {
+                self.marked.insert(path);
+            }
+        }
+    }
+
+    // the marked results in order, or the one under the cursor
+    fn picked(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            return self
+                .current()
+                .map(|item| vec![item.hit.path.clone()])
+                .unwrap_or_default();
+        }
+
+        self.items
+            .iter()
+            .filter(|item| self.marked.contains(&item.hit.path))
+            .map(|item| item.hit.path.clone())
+            .collect()
+    }
+
+    fn draw<W: Write>(&mut self, out: &mut W) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        self.rows = height.saturating_sub(HEADER) as usize;
+
+        // keep the cursor on the screen
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if self.cursor >= self.offset + self.rows {
+            self.offset = self.cursor + 1 - self.rows;
+        }
+
+        let mut status = format!("{}/{}", self.shown.len(), self.items.len());
+        if !self.marked.is_empty() {
+            status.push_str(&format!(" ({} selected)", self.marked.len()));
+        }
+        if self.searching {
+            status.push_str(" searching");
+        }
+        queue!(
+            out,
+            cursor::MoveTo(0, 1),
+            terminal::Clear(ClearType::CurrentLine),
+            Print(status.dimmed())
+        )?;
+
+        for row in 0..self.rows {
+            queue!(
+                out,
+                cursor::MoveTo(0, HEADER + row as u16),
+                terminal::Clear(ClearType::CurrentLine)
+            )?;
+
+            let position = self.offset + row;
+            if let Some(&index) = self.shown.get(position) {
+                let item = &self.items[index];
+                let marked = self.marked.contains(&item.hit.path);
+                let line = render(item, position == self.cursor, marked, width as usize);
+                queue!(out, Print(line))?;
+            }
+        }
+
+        // the query last, to leave the cursor behind it
+        queue!(
+            out,
+            cursor::MoveTo(0, 0),
+            terminal::Clear(ClearType::CurrentLine),
+            Print("> ".truecolor(250, 0, 104).bold()),
+            Print(&self.query),
+            cursor::Show
+        )?;
+        out.flush()
+    }
+}
+
+// pointer, mark and path, too long parents are cut from the left
+fn render(item: &Item, current: bool, marked: bool, width: usize) -> String {
+    let pointer = if current {
+        ">".truecolor(250, 0, 104).bold().to_string()
+    } else {
+        " ".to_string()
+    };
+    let mark = if marked {
+        "+".truecolor(59, 179, 140).bold().to_string()
+    } else {
+        " ".to_string()
+    };
+
+    let parent = item.hit.parent();
+    let name = &item.hit.name;
+    let available = width.saturating_sub(3);
+    let (parent_len, name_len) = (parent.chars().count(), name.chars().count());
+
+    let path = if parent_len + 1 + name_len <= available {
+        format!(
+            "{}/{}",
+            parent.dimmed(),
+            crate::highlight_pattern_in_name(name, &item.spans).truecolor(59, 179, 140)
+        )
+    } else if name_len + 2 <= available {
+        let tail = parent
+            .chars()
+            .skip(parent_len + name_len + 2 - available)
+            .collect::<String>();
+        format!(
+            "{}/{}",
+            format!("…{tail}").dimmed(),
+            crate::highlight_pattern_in_name(name, &item.spans).truecolor(59, 179, 140)
+        )
+    } else {
+        let name = name.chars().take(available).collect::<String>();
+        name.truecolor(59, 179, 140).to_string()
+    };
+
+    format!("{pointer}{mark} {path}")
+}
@@ -0,0 +1,1153 @@
+//! Simple file search
+//!
+//! The matching and filtering behind the `sf` binary.
+//! Configure a search with [`SearchBuilder`] and iterate over the [`Hit`]s of the resulting [`Searcher`].
+//!
+//! ```no_run
+//! let mut searcher = sf::SearchBuilder::new("main", ".")
+//!     .file_only(true)
+//!     .extensions(["rs"])
+//!     .build()
+//!     .unwrap();
+//!
+//! for hit in searcher.by_ref().flatten() {
+//!     println!("{}", hit.path.display());
+//! }
+//!
+//! println!("{} matches", searcher.stats().search_hits);
+//! ```
+
+use walkdir::{DirEntry, WalkDir};
+
+use std::{
+    env, error,
+    ffi::OsStr,
+    fmt, fs, io,
+    ops::Range,
+    path::{self, Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+mod content;
+mod entry;
+mod fold;
+mod fuzzy;
+mod ignores;
+mod index;
+mod matcher;
+mod parallel;
+mod size;
+#[cfg(test)]
+mod temp_dir;
+mod time;
+mod types;
+
+pub use content::LineMatch;
+use entry::{Entry, PathEntry};
+use ignores::Ignores;
+use index::IndexWalk;
+pub use index::{Index, IndexError, IndexStats};
+pub use matcher::MatchMode;
+use matcher::Matcher;
+use parallel::WalkError;
+pub use size::{ParseSizeError, SizeFilter};
+pub use time::{FileTime, ParseTimeError, TimeFilter};
+pub use types::{EntryType, ParseTypeError};
+
+/// Default maximum search depth
+pub const DEFAULT_DEPTH: u32 = 250;
+
+// hits buffered between the parallel walker and the consumer
+const CHANNEL_CAPACITY: usize = 4096;
+
+// windows file attribute marking an entry as hidden
+#[cfg(windows)]
+const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+
+/// What counts as a hidden entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HiddenMode {
+    /// The platform convention: dotfiles on unix, the hidden attribute on windows
+    #[default]
+    Native,
+    /// Dotfiles and entries with the hidden attribute
+    Both,
+}
+
+/// Type of a matched entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    File,
+    Dir,
+    Symlink,
+    Other,
+}
+
+impl FileKind {
+    /// Lowercase name, e.g. for machine-readable output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileKind::File => "file",
+            FileKind::Dir => "dir",
+            FileKind::Symlink => "symlink",
+            FileKind::Other => "other",
+        }
+    }
+
+    fn from_entry<E: Entry>(entry: &E) -> Self {
+        let file_type = entry.file_type();
+        if file_type.is_symlink() {
+            FileKind::Symlink
+        } else if file_type.is_dir() {
+            FileKind::Dir
+        } else if file_type.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        }
+    }
+}
+
+/// The filter an entry passed to become a hit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchedFilter {
+    /// The name contains the search pattern
+    Pattern,
+    /// The name contains the search pattern and has one of the given extensions
+    Extension(String),
+}
+
+/// A single search result
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub path: PathBuf,
+    pub name: String,
+    pub file_kind: FileKind,
+    pub depth: usize,
+    pub matched: MatchedFilter,
+    /// Score of a fuzzy match, higher is better
+    pub score: Option<i64>,
+    /// Lines containing the pattern, only filled when searching file contents
+    pub lines: Vec<LineMatch>,
+    /// A followed symlink or reached through one, see [`SearchBuilder::follow`]
+    pub via_link: bool,
+    /// Size of a file in bytes, only known with size or time filters or [`SearchBuilder::count_bytes`]
+    pub size: Option<u64>,
+}
+
+impl Hit {
+    /// Parent directory of the hit with `/` as separator
+    pub fn parent(&self) -> String {
+        self.path
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .to_string_lossy()
+            .replace('\\', "/")
+    }
+}
+
+/// Counters of a running or finished search
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub entry_count: u64,
+    /// Directories walked, also the ones not counted as entries, e.g. with [`SearchBuilder::file_only`]
+    pub dir_count: u64,
+    pub error_count: u64,
+    pub search_hits: u64,
+    /// Total size of the matched files, only counted with [`SearchBuilder::count_bytes`]
+    pub matched_bytes: u64,
+}
+
+// shared between the worker threads of a parallel search
+#[derive(Debug, Default)]
+struct Counters {
+    entry_count: AtomicU64,
+    dir_count: AtomicU64,
+    error_count: AtomicU64,
+    search_hits: AtomicU64,
+    matched_bytes: AtomicU64,
+}
+
+impl Counters {
+    fn inc(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> Stats {
+        Stats {
+            entry_count: self.entry_count.load(Ordering::Relaxed),
+            dir_count: self.dir_count.load(Ordering::Relaxed),
+            error_count: self.error_count.load(Ordering::Relaxed),
+            search_hits: self.search_hits.load(Ordering::Relaxed),
+            matched_bytes: self.matched_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// An entry that could not be read during the search
+#[derive(Debug)]
+pub struct SearchError {
+    inner: ErrorInner,
+}
+
+#[derive(Debug)]
+enum ErrorInner {
+    // walking the directory tree failed
+    Walk(walkdir::Error),
+    // reading a directory in parallel or the content of a file failed
+    Io { path: PathBuf, err: io::Error },
+    // the parallel walker followed a symlink to one of its ancestors
+    Loop { path: PathBuf, ancestor: PathBuf },
+}
+
+impl SearchError {
+    fn io(path: PathBuf, err: io::Error) -> Self {
+        Self {
+            inner: ErrorInner::Io { path, err },
+        }
+    }
+
+    /// Path of the entry that caused the error, if known
+    pub fn path(&self) -> Option<&Path> {
+        match &self.inner {
+            ErrorInner::Walk(err) => err.path(),
+            ErrorInner::Io { path, .. } | ErrorInner::Loop { path, .. } => Some(path),
+        }
+    }
+
+    /// Underlying io error, if there is one
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match &self.inner {
+            ErrorInner::Walk(err) => err.io_error(),
+            ErrorInner::Io { err, .. } => Some(err),
+            ErrorInner::Loop { .. } => None,
+        }
+    }
+
+    /// The directory a followed symlink points back to, if the error is a symlink loop
+    pub fn loop_ancestor(&self) -> Option<&Path> {
+        match &self.inner {
+            ErrorInner::Walk(err) => err.loop_ancestor(),
+            ErrorInner::Loop { ancestor, .. } => Some(ancestor),
+            ErrorInner::Io { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.inner {
+            ErrorInner::Walk(err) => err.fmt(f),
+            ErrorInner::Io { path, err } => {
+                write!(f, "Unable to read {}: {}", path.display(), err)
+            }
+            ErrorInner::Loop { path, ancestor } => write!(
+                f,
+                "File system loop found: {} points to an ancestor {}",
+                path.display(),
+                ancestor.display()
+            ),
+        }
+    }
+}
+
+impl error::Error for SearchError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match &self.inner {
+            ErrorInner::Walk(err) => Some(err),
+            ErrorInner::Io { err, .. } => Some(err),
+            ErrorInner::Loop { .. } => None,
+        }
+    }
+}
+
+impl From<walkdir::Error> for SearchError {
+    fn from(err: walkdir::Error) -> Self {
+        Self {
+            inner: ErrorInner::Walk(err),
+        }
+    }
+}
+
+impl From<WalkError> for SearchError {
+    fn from(err: WalkError) -> Self {
+        let inner = match err {
+            WalkError::Io { path, err } => ErrorInner::Io { path, err },
+            WalkError::Loop { path, ancestor } => ErrorInner::Loop { path, ancestor },
+        };
+        Self { inner }
+    }
+}
+
+/// Reasons why a [`SearchBuilder`] can't start a search
+#[derive(Debug)]
+pub enum BuildError {
+    /// `.` was given as path, but the current directory is not accessible
+    CurrentDir(io::Error),
+    /// A pattern is not a valid regular expression
+    Regex(regex::Error),
+    /// The search pattern is not a valid glob
+    Glob(globset::Error),
+    /// The search path doesn't lie within the [`Index`] to search
+    NotIndexed(PathBuf),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::CurrentDir(err) => write!(f, "Unable to get current directory: {err}"),
+            BuildError::Regex(err) => write!(f, "Invalid regular expression: {err}"),
+            BuildError::Glob(err) => write!(f, "Invalid glob: {err}"),
+            BuildError::NotIndexed(path) => write!(
+                f,
+                "{} is not indexed, add it via sf index build",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            BuildError::CurrentDir(err) => Some(err),
+            BuildError::Regex(err) => Some(err),
+            BuildError::Glob(err) => Some(err),
+            BuildError::NotIndexed(_) => None,
+        }
+    }
+}
+
+/// Builder for a [`Searcher`]
+#[derive(Debug, Clone)]
+pub struct SearchBuilder {
+    pattern: String,
+    path: PathBuf,
+    file_only: bool,
+    dir_only: bool,
+    no_hidden: bool,
+    hidden_mode: HiddenMode,
+    ignore_files: bool,
+    case_insensitive: bool,
+    smart_case: bool,
+    match_mode: MatchMode,
+    exclude_regex: bool,
+    content: bool,
+    depth: u32,
+    threads: usize,
+    sort_by_path: bool,
+    follow: bool,
+    extensions: Vec<String>,
+    exclude: Vec<String>,
+    types: Vec<EntryType>,
+    sizes: Vec<SizeFilter>,
+    times: Vec<TimeFilter>,
+    count_bytes: bool,
+    index: Option<Arc<Index>>,
+}
+
+impl SearchBuilder {
+    /// Search for `pattern` in the names of all entries below `path`
+    ///
+    /// `.` is accepted as the current directory.
+    pub fn new(pattern: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            path: path.into(),
+            file_only: false,
+            dir_only: false,
+            no_hidden: false,
+            hidden_mode: HiddenMode::default(),
+            ignore_files: true,
+            case_insensitive: false,
+            smart_case: false,
+            match_mode: MatchMode::default(),
+            exclude_regex: false,
+            content: false,
+            depth: DEFAULT_DEPTH,
+            threads: 1,
+            sort_by_path: false,
+            follow: false,
+            extensions: Vec::new(),
+            exclude: Vec::new(),
+            types: Vec::new(),
+            sizes: Vec::new(),
+            times: Vec::new(),
+            count_bytes: false,
+            index: None,
+        }
+    }
+
+    /// Only match files
+    pub fn file_only(mut self, yes: bool) -> Self {
+        self.file_only = yes;
+        self
+    }
+
+    /// Only match directories
+    pub fn dir_only(mut self, yes: bool) -> Self {
+        self.dir_only = yes;
+        self
+    }
+
+    /// Skip hidden entries and the content of hidden directories
+    pub fn no_hidden(mut self, yes: bool) -> Self {
+        self.no_hidden = yes;
+        self
+    }
+
+    /// Set what counts as hidden for [`SearchBuilder::no_hidden`]
+    pub fn hidden_mode(mut self, hidden_mode: HiddenMode) -> Self {
+        self.hidden_mode = hidden_mode;
+        self
+    }
+
+    /// Skip entries listed in `.gitignore`, `.ignore` and `.sfignore` files and the global git excludes
+    ///
+    /// Enabled by default. Ignored directories are not entered and `.git` directories are skipped.
+    /// Ignore files above the search path are respected up to the root of the git repository.
+    pub fn ignore_files(mut self, yes: bool) -> Self {
+        self.ignore_files = yes;
+        self
+    }
+
+    /// Match the pattern and the exclude patterns case insensitively
+    ///
+    /// Names with non-ASCII characters are compared with Unicode case folding, so `straße` matches `STRASSE`
+    /// and `ss` matches `Straße`. The folding is lowercasing plus the common special cases like `ß` and `ﬁ`,
+    /// not the complete Unicode table.
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.case_insensitive = yes;
+        self
+    }
+
+    /// Match case insensitively if the search pattern has no uppercase characters
+    ///
+    /// Decides for the exclude patterns as well. [`SearchBuilder::case_insensitive`] takes precedence.
+    /// Escape sequences like `\D` don't count as uppercase in [`MatchMode::Regex`].
+    pub fn smart_case(mut self, yes: bool) -> Self {
+        self.smart_case = yes;
+        self
+    }
+
+    /// Set how the search pattern is matched against entry names
+    pub fn match_mode(mut self, match_mode: MatchMode) -> Self {
+        self.match_mode = match_mode;
+        self
+    }
+
+    /// Treat the exclude patterns as regular expressions
+    pub fn exclude_regex(mut self, yes: bool) -> Self {
+        self.exclude_regex = yes;
+        self
+    }
+
+    /// Search for the pattern in the content of files instead of in the names
+    ///
+    /// The exclude patterns still apply to the names, binary files are skipped.
+    /// Matching lines end up in [`Hit::lines`].
+    pub fn content(mut self, yes: bool) -> Self {
+        self.content = yes;
+        self
+    }
+
+    /// Set the maximum search depth
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Walk the directory tree with `threads` worker threads
+    ///
+    /// `0` uses one thread per available core, `1` walks sequentially in the calling thread.
+    /// Hits of a parallel search come in no particular order.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Read the entries of every directory in file name order, so hits come in path order
+    ///
+    /// Only applies to the sequential walk, see [`SearchBuilder::threads`].
+    pub fn sort_by_path(mut self, yes: bool) -> Self {
+        self.sort_by_path = yes;
+        self
+    }
+
+    /// Follow symlinks and search the content of linked directories
+    ///
+    /// Links pointing to one of their ancestors are yielded as [`SearchError`]s, see [`SearchError::loop_ancestor`].
+    /// Broken links are yielded as errors as well.
+    pub fn follow(mut self, yes: bool) -> Self {
+        self.follow = yes;
+        self
+    }
+
+    /// Only match entries with one of the given extensions
+    pub fn extensions<I, S>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.extensions = extensions.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only match entries of any of the given types
+    ///
+    /// Applies on top of [`SearchBuilder::file_only`] and [`SearchBuilder::dir_only`].
+    pub fn types(mut self, types: impl IntoIterator<Item = EntryType>) -> Self {
+        self.types = types.into_iter().collect();
+        self
+    }
+
+    /// Only match files passing all of the given size filters
+    pub fn sizes(mut self, sizes: impl IntoIterator<Item = SizeFilter>) -> Self {
+        self.sizes = sizes.into_iter().collect();
+        self
+    }
+
+    /// Only match entries passing all of the given time filters
+    pub fn times(mut self, times: impl IntoIterator<Item = TimeFilter>) -> Self {
+        self.times = times.into_iter().collect();
+        self
+    }
+
+    /// Sum up the size of the matched files in [`Stats::matched_bytes`]
+    ///
+    /// Reads the metadata of every matched file.
+    pub fn count_bytes(mut self, yes: bool) -> Self {
+        self.count_bytes = yes;
+        self
+    }
+
+    /// Search the entries of a prebuilt [`Index`] instead of walking the directory tree
+    ///
+    /// The search path has to lie within one of the indexed directories.
+    /// Only entries whose name matches are read from disk, entries removed since the last update are skipped.
+    /// Entries come in path order, [`SearchBuilder::threads`] and [`SearchBuilder::follow`] don't apply.
+    pub fn index(mut self, index: Arc<Index>) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Skip entries whose name contains any of the given patterns
+    pub fn exclude<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.exclude = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Compile the patterns and start walking the search path
+    ///
+    /// Fails if a regular expression doesn't compile, if `.` was given as path and the current directory
+    /// is not accessible or if the search path is not part of the [`Index`] to search.
+    pub fn build(self) -> Result<Searcher, BuildError> {
+        let mut search_path = self.path.clone();
+
+        // accept "." as current directory
+        if search_path.as_path() == Path::new(".") {
+            search_path = env::current_dir().map_err(BuildError::CurrentDir)?;
+        }
+
+        // handle case-insensitive and smart-case flag
+        // the search pattern decides for the exclude patterns as well
+        let case_insensitive = self.case_insensitive
+            || (self.smart_case
+                && !matcher::has_uppercase(&self.pattern, self.match_mode == MatchMode::Regex));
+
+        // store search pattern in aho-corasick builder or compile it as regex or glob
+        let patterns = [self.pattern.clone()];
+        let pattern_matcher = match self.match_mode {
+            MatchMode::Literal => Matcher::literal(&patterns, case_insensitive),
+            MatchMode::Regex => {
+                Matcher::regex(&patterns, case_insensitive).map_err(BuildError::Regex)?
+            }
+            MatchMode::Glob => {
+                Matcher::glob(&self.pattern, case_insensitive).map_err(BuildError::Glob)?
+            }
+            MatchMode::Fuzzy => Matcher::fuzzy(&self.pattern, case_insensitive),
+        };
+
+        // store exclude patterns in aho-corasick builder or compile them as regex
+        let exclude_matcher = if self.exclude_regex {
+            Matcher::regex(&self.exclude, case_insensitive).map_err(BuildError::Regex)?
+        } else {
+            Matcher::literal(&self.exclude, case_insensitive)
+        };
+
+        let config = Arc::new(Config {
+            absolute_path: path::absolute(&search_path).unwrap_or_else(|_| search_path.clone()),
+            search_path: search_path.clone(),
+            file_flag: self.file_only,
+            content_flag: self.content,
+            dir_flag: self.dir_only,
+            no_hidden_flag: self.no_hidden,
+            hidden_mode: self.hidden_mode,
+            ignores: self.ignore_files.then(|| Ignores::new(&search_path)),
+            pattern: self.pattern,
+            pattern_matcher,
+            extensions: self.extensions,
+            exclude_matcher,
+            type_filters: self.types,
+            size_filters: self.sizes,
+            time_filters: self.times,
+            count_bytes_flag: self.count_bytes,
+            follow_flag: self.follow,
+            max_depth: self.depth as usize,
+        });
+        let max_depth = config.max_depth;
+
+        let counters = Arc::new(Counters::default());
+
+        let threads = match self.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+
+        let walk = if let Some(index) = self.index {
+            let walk = IndexWalk::new(
+                index,
+                &search_path,
+                max_depth,
+                self.no_hidden,
+                self.hidden_mode,
+                self.ignore_files,
+            )
+            .ok_or_else(|| BuildError::NotIndexed(search_path.clone()))?;
+
+            Walk::Index(walk)
+        } else if threads > 1 {
+            let (tx, rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+            let filter_config = Arc::clone(&config);
+            let check_config = Arc::clone(&config);
+            let check_counters = Arc::clone(&counters);
+
+            parallel::spawn(
+                search_path,
+                max_depth,
+                threads,
+                self.follow,
+                move |e| file_check(e, &filter_config), // handle hidden flag
+                move |result| {
+                    let hit = match result {
+                        Ok(entry) => {
+                            let via_link = entry.via_link();
+                            match check_entry(entry, via_link, &check_config, &check_counters) {
+                                Ok(Some(hit)) => Ok(hit),
+                                Ok(None) => return true,
+                                Err(err) => Err(err),
+                            }
+                        }
+                        Err(err) => {
+                            // count errors
+                            Counters::inc(&check_counters.error_count);
+                            Err(err.into())
+                        }
+                    };
+
+                    // the searcher was dropped, stop walking
+                    tx.send(hit).is_ok()
+                },
+            );
+
+            Walk::Parallel(rx)
+        } else {
+            // filter files
+            let filter_config = Arc::clone(&config);
+            let mut walkdir = WalkDir::new(search_path)
+                .max_depth(max_depth) // set maximum search depth
+                .follow_links(self.follow); // walkdir reports symlink loops as errors
+            if self.sort_by_path {
+                walkdir = walkdir.sort_by_file_name();
+            }
+
+            let walker = walkdir
+                .into_iter()
+                // TODO bottleneck if it has to filter out hidden files
+                .filter_entry(
+                    Box::new(move |e: &DirEntry| file_check(e, &filter_config)) as EntryFilter
+                ); // handle hidden flag
+
+            Walk::Sequential(walker)
+        };
+
+        Ok(Searcher {
+            config,
+            walk,
+            counters,
+            link_depths: Vec::new(),
+        })
+    }
+}
+
+type EntryFilter = Box<dyn FnMut(&DirEntry) -> bool + Send>;
+
+struct Config {
+    search_path: PathBuf,
+    // e.g. to compare with the paths reported by a file watcher
+    absolute_path: PathBuf,
+    file_flag: bool,
+    content_flag: bool,
+    dir_flag: bool,
+    no_hidden_flag: bool,
+    hidden_mode: HiddenMode,
+    ignores: Option<Ignores>,
+    pattern: String,
+    pattern_matcher: Matcher,
+    extensions: Vec<String>,
+    exclude_matcher: Matcher,
+    type_filters: Vec<EntryType>,
+    size_filters: Vec<SizeFilter>,
+    time_filters: Vec<TimeFilter>,
+    count_bytes_flag: bool,
+    follow_flag: bool,
+    max_depth: usize,
+}
+
+enum Walk {
+    Sequential(walkdir::FilterEntry<walkdir::IntoIter, EntryFilter>),
+    // hits and errors checked by the worker threads
+    Parallel(mpsc::Receiver<Result<Hit, SearchError>>),
+    Index(IndexWalk),
+}
+
+/// Iterator over the hits of a search
+///
+/// Entries that can't be read are yielded as [`SearchError`]s.
+pub struct Searcher {
+    config: Arc<Config>,
+    walk: Walk,
+    counters: Arc<Counters>,
+    // depths of the followed directory symlinks above the current entry of a sequential walk
+    link_depths: Vec<usize>,
+}
+
+impl Searcher {
+    /// The search pattern
+    pub fn pattern(&self) -> &str {
+        &self.config.pattern
+    }
+
+    /// The search path, `.` is resolved to the current directory
+    pub fn path(&self) -> &Path {
+        &self.config.search_path
+    }
+
+    /// Check a single entry below the search path as if the walk had found it, e.g. one reported by a file watcher
+    ///
+    /// All filters apply, including the search depth and hidden or ignored directories above the entry.
+    /// Symlinks are never followed. Entries that don't exist (anymore) don't match.
+    ///
+    /// The path can be given like the search path or as absolute path, see [`Searcher::walk_path`].
+    pub fn check_path(&self, path: &Path) -> Result<Option<Hit>, SearchError> {
+        let config = &self.config;
+        let Some(path) = self.walk_path(path) else {
+            return Ok(None);
+        };
+        let path = path.as_path();
+        let relative = path
+            .strip_prefix(&config.search_path)
+            .expect("below the search path");
+        let depth = relative.components().count();
+        if depth > config.max_depth {
+            return Ok(None);
+        }
+
+        // the walk doesn't enter hidden or ignored directories, the entry itself is checked last
+        let mut ancestors = path.ancestors().take(depth + 1).collect::<Vec<_>>();
+        ancestors.reverse();
+        for (depth, current) in ancestors.into_iter().enumerate() {
+            let metadata = match fs::symlink_metadata(current) {
+                Ok(metadata) => metadata,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+                Err(err) => {
+                    // count errors
+                    Counters::inc(&self.counters.error_count);
+                    return Err(SearchError::io(current.to_path_buf(), err));
+                }
+            };
+
+            let entry = PathEntry::new(current.to_path_buf(), depth, metadata);
+            if !file_check(&entry, config) {
+                return Ok(None);
+            }
+            if current == path {
+                return check_entry(entry, false, config, &self.counters);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// The path the walk reports for an entry below the search path, None for entries outside of it
+    ///
+    /// Accepts absolute paths for a relative search path as well, e.g. the ones reported by a file watcher.
+    pub fn walk_path(&self, path: &Path) -> Option<PathBuf> {
+        let config = &self.config;
+        if path.starts_with(&config.search_path) {
+            return Some(path.to_path_buf());
+        }
+
+        let relative = path.strip_prefix(&config.absolute_path).ok()?;
+        if relative.as_os_str().is_empty() {
+            Some(config.search_path.clone())
+        } else {
+            Some(config.search_path.join(relative))
+        }
+    }
+
+    /// Hits need to be sorted by [`Hit::score`] to be ranked
+    pub fn is_ranked(&self) -> bool {
+        matches!(self.config.pattern_matcher, Matcher::Fuzzy { .. })
+    }
+
+    /// Byte ranges in `name` to highlight as matching the search pattern
+    pub fn match_spans(&self, name: &str) -> Vec<Range<usize>> {
+        match &self.config.pattern_matcher {
+            // every occurrence, also if matched case insensitively
+            Matcher::Literal(ac) => matcher::literal_spans(ac, name),
+            Matcher::Folded(ac) => matcher::folded_spans(ac, name),
+            Matcher::Regex(re) => matcher::regex_spans(re, name),
+            // a glob matches the whole name, nothing to point out
+            Matcher::Glob { .. } => Vec::new(),
+            // every matched character
+            Matcher::Fuzzy { .. } => self
+                .config
+                .pattern_matcher
+                .fuzzy_match(name)
+                .map(|m| m.spans)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Counters of the search so far, exact once the iterator is exhausted
+    pub fn stats(&self) -> Stats {
+        self.counters.snapshot()
+    }
+}
+
+fn check_entry<E: Entry>(
+    entry: E,
+    via_link: bool,
+    config: &Config,
+    counters: &Counters,
+) -> Result<Option<Hit>, SearchError> {
+    // count walked directories before the file flag skips them
+    if entry.file_type().is_dir() {
+        Counters::inc(&counters.dir_count);
+    }
+
+    // handle file flag, only files have a content
+    // must be outside of function file_check()
+    // else no file will be searched with WalkDir...filter_entry()
+    if (config.file_flag || config.content_flag) && !entry.file_type().is_file() {
+        return Ok(None);
+    }
+
+    // handle dir flag
+    // must be outside of function file_check()
+    // else search stops if dir is found via WalkDir...filter_entry()
+    if config.dir_flag && !entry.file_type().is_dir() {
+        return Ok(None);
+    }
+
+    // count searched entries
+    Counters::inc(&counters.entry_count);
+
+    // handle possible file extensions
+    let matched = if config.extensions.is_empty() {
+        MatchedFilter::Pattern
+    } else {
+        // check if entry extension matches any given extension via extensions flag
+        let entry_extension = match entry.path().extension() {
+            Some(extension) => extension.to_string_lossy(),
+            None => return Ok(None),
+        };
+        match config
+            .extensions
+            .iter()
+            .find(|it| entry_extension == it.as_str())
+        {
+            Some(extension) => MatchedFilter::Extension(extension.to_owned()),
+            None => return Ok(None),
+        }
+    };
+
+    let name = entry.file_name().to_string_lossy().to_string();
+    if config.exclude_matcher.is_match(&name) {
+        return Ok(None);
+    }
+
+    // check for pattern match in filename via aho-corasick algorithm, regex or glob
+    // before the filters reading the metadata or the directory, the content is read last
+    if !config.content_flag {
+        let pattern_match = if config.pattern_matcher.on_path() {
+            // path relative to the search path with "/" as separator
+            let relative = entry
+                .path()
+                .strip_prefix(&config.search_path)
+                .unwrap_or_else(|_| entry.path())
+                .to_string_lossy()
+                .replace('\\', "/");
+            config.pattern_matcher.is_match(&relative)
+        } else {
+            config.pattern_matcher.is_match(&name)
+        };
+
+        if !pattern_match {
+            return Ok(None);
+        }
+    }
+
+    // handle type filters, any of them has to match
+    if !config.type_filters.is_empty()
+        && !config.type_filters.iter().any(|kind| kind.matches(&entry))
+    {
+        return Ok(None);
+    }
+
+    // handle size and time filters, only files have a size
+    let mut size = None;
+    if !config.size_filters.is_empty() || !config.time_filters.is_empty() {
+        let is_file = entry.file_type().is_file();
+        if !config.size_filters.is_empty() && !is_file {
+            return Ok(None);
+        }
+
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                // count errors
+                Counters::inc(&counters.error_count);
+                return Err(SearchError::io(entry.into_path(), err));
+            }
+        };
+        if !config
+            .size_filters
+            .iter()
+            .all(|filter| filter.matches(metadata.len()))
+            || !config
+                .time_filters
+                .iter()
+                .all(|filter| filter.matches(&metadata))
+        {
+            return Ok(None);
+        }
+        size = is_file.then_some(metadata.len());
+    }
+
+    let lines = if config.content_flag {
+        // check for pattern match in every line of the file, skip binary files
+        match content::search_file(entry.path(), &config.pattern_matcher) {
+            Ok(Some(lines)) if !lines.is_empty() => lines,
+            Ok(_) => return Ok(None),
+            Err(err) => {
+                // count errors
+                Counters::inc(&counters.error_count);
+                return Err(SearchError::io(entry.into_path(), err));
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    Counters::inc(&counters.search_hits);
+
+    // sum up the size of matched files
+    if config.count_bytes_flag && entry.file_type().is_file() {
+        if size.is_none() {
+            size = entry.metadata().ok().map(|metadata| metadata.len());
+        }
+        counters
+            .matched_bytes
+            .fetch_add(size.unwrap_or(0), Ordering::Relaxed);
+    }
+
+    let score = config.pattern_matcher.fuzzy_match(&name).map(|m| m.score);
+
+    Ok(Some(Hit {
+        file_kind: FileKind::from_entry(&entry),
+        depth: entry.depth(),
+        path: entry.into_path(),
+        name,
+        matched,
+        score,
+        lines,
+        size,
+        via_link,
+    }))
+}
+
+impl Iterator for Searcher {
+    type Item = Result<Hit, SearchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let walker = match &mut self.walk {
+            Walk::Sequential(walker) => walker,
+            // the channel closes once the walk is finished
+            Walk::Parallel(rx) => return rx.recv().ok(),
+            Walk::Index(walk) => return next_indexed(walk, &self.config, &self.counters),
+        };
+
+        loop {
+            match walker.next()? {
+                Ok(entry) => {
+                    let via_link =
+                        self.config.follow_flag && via_link(&mut self.link_depths, &entry);
+                    match check_entry(entry, via_link, &self.config, &self.counters) {
+                        Ok(Some(hit)) => return Some(Ok(hit)),
+                        Ok(None) => {}
+                        Err(err) => return Some(Err(err)),
+                    }
+                }
+                Err(err) => {
+                    // count errors
+                    Counters::inc(&self.counters.error_count);
+                    return Some(Err(err.into()));
+                }
+            }
+        }
+    }
+}
+
+// only entries passing the checks on their indexed name and type are read from disk
+fn next_indexed(
+    walk: &mut IndexWalk,
+    config: &Config,
+    counters: &Counters,
+) -> Option<Result<Hit, SearchError>> {
+    loop {
+        let (path, depth) =
+            walk.next_accepted(|name, kind| index_check(name, kind, config, counters))?;
+        let metadata = match fs::symlink_metadata(&path) {
+            Ok(metadata) => metadata,
+            // removed since the index was built
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                // count errors
+                Counters::inc(&counters.error_count);
+                return Some(Err(SearchError::io(path, err)));
+            }
+        };
+
+        match check_entry(
+            PathEntry::new(path, depth, metadata),
+            false,
+            config,
+            counters,
+        ) {
+            Ok(Some(hit)) => return Some(Ok(hit)),
+            Ok(None) => {}
+            Err(err) => return Some(Err(err)),
+        }
+    }
+}
+
+// the part of check_entry that only needs the name and the type
+// rejected entries are counted like check_entry does
+fn index_check(name: &OsStr, kind: FileKind, config: &Config, counters: &Counters) -> bool {
+    // contents and paths can't be checked without the entry
+    if config.content_flag || config.pattern_matcher.on_path() {
+        return true;
+    }
+
+    let wrong_kind =
+        (config.file_flag && kind != FileKind::File) || (config.dir_flag && kind != FileKind::Dir);
+    if !wrong_kind && config.pattern_matcher.is_match(&name.to_string_lossy()) {
+        return true;
+    }
+
+    if kind == FileKind::Dir {
+        Counters::inc(&counters.dir_count);
+    }
+    if !wrong_kind {
+        Counters::inc(&counters.entry_count);
+    }
+    false
+}
+
+// walkdir yields a directory right before its content
+// so the followed directory symlinks above an entry are the ones with a smaller depth
+fn via_link(link_depths: &mut Vec<usize>, entry: &DirEntry) -> bool {
+    while link_depths
+        .last()
+        .is_some_and(|&depth| depth >= entry.depth())
+    {
+        link_depths.pop();
+    }
+
+    // the search path itself is always followed
+    let is_link = entry.depth() > 0 && entry.path_is_symlink();
+    if is_link && entry.file_type().is_dir() {
+        link_depths.push(entry.depth());
+    }
+
+    is_link || !link_depths.is_empty()
+}
+
+// check entries if hidden or ignored and compare to hidden and ignore flag
+// rejected directories are not entered
+fn file_check<E: Entry>(entry: &E, config: &Config) -> bool {
+    // never skip the search path itself, even if it is hidden (e.g. ~/.config)
+    if entry.depth() == 0 {
+        return true;
+    }
+
+    if config.no_hidden_flag && is_hidden(entry, config.hidden_mode) {
+        return false;
+    }
+
+    if let Some(ignores) = &config.ignores {
+        if ignores.is_ignored(entry.path(), entry.file_type().is_dir()) {
+            return false;
+        }
+    }
+
+    true
+}
+
+fn is_hidden<E: Entry>(entry: &E, hidden_mode: HiddenMode) -> bool {
+    match hidden_mode {
+        HiddenMode::Native => is_hidden_native(entry),
+        HiddenMode::Both => is_dotfile(entry) || has_hidden_attribute(entry),
+    }
+}
+
+#[cfg(windows)]
+fn is_hidden_native<E: Entry>(entry: &E) -> bool {
+    has_hidden_attribute(entry)
+}
+
+#[cfg(not(windows))]
+fn is_hidden_native<E: Entry>(entry: &E) -> bool {
+    is_dotfile(entry)
+}
+
+// only looks at the name, no metadata needed
+fn is_dotfile<E: Entry>(entry: &E) -> bool {
+    entry.file_name().to_string_lossy().starts_with('.')
+}
+
+// TODO bottleneck
+#[cfg(windows)]
+fn has_hidden_attribute<E: Entry>(entry: &E) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    match entry.metadata() {
+        Ok(metadata) => (metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN) > 0,
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(windows))]
+fn has_hidden_attribute<E: Entry>(_entry: &E) -> bool {
+    false
+}
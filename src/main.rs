@@ -1,71 +1,119 @@
-use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
-use clap::{Arg, ArgAction, Command};
+use clap::{builder::PossibleValue, Arg, ArgAction, Command};
+use dupes::{DupeSet, Dupes};
+use exec::{Exec, ExecMode, ExecStatus, Runner};
 use flexi_logger::{detailed_format, Duplicate, FileSpec, Logger};
-use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use indicatif::{HumanBytes, HumanDuration, ProgressBar, ProgressStyle};
 use log::{error, warn};
 use owo_colors::colored::*;
-use walkdir::{DirEntry, WalkDir};
+use serde_json::json;
+use settings::Settings;
+use sf::{
+    EntryType, FileTime, HiddenMode, Hit, Index, IndexError, IndexStats, MatchMode, SearchBuilder,
+    SearchError, Searcher, SizeFilter, Stats, TimeFilter, DEFAULT_DEPTH,
+};
+
+use interactive::{Item, Outcome};
+use report::Report;
+use walkdir::WalkDir;
+use watch::{Change, Watch};
 
 use std::{
-    env, fs,
-    io::{self, Write},
-    os::windows::prelude::MetadataExt,
-    path::{Path, PathBuf},
+    collections::BTreeSet,
+    fs,
+    io::{self, BufRead, Write},
+    ops::Range,
+    path::{self, Path, PathBuf},
     process,
-    time::{Duration, Instant},
+    sync::{mpsc, Arc},
+    thread,
+    time::{Duration, Instant, UNIX_EPOCH},
 };
 
+mod dupes;
+mod exec;
+mod interactive;
+mod report;
+mod settings;
+#[cfg(test)]
+mod temp_dir;
+mod watch;
+
 const BUFFER_CAPACITY: usize = 64 * (1 << 10); // 64 KB
 
+// search path to read a list of search paths from stdin
+const STDIN_PATH: &str = "-";
+
+// prebuilt index in the config directory, see sf index
+const INDEX_FILE: &str = "sf.index";
+
+// extensions listed by --stats-long, the others are only counted
+const TOP_EXTENSIONS: usize = 10;
+
+// order of the search results via --sort
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Name,
+    Path,
+    Size,
+    Mtime,
+    Depth,
+    Ext,
+}
+
+impl SortKey {
+    fn from_value(value: &str) -> Option<Self> {
+        match value {
+            "name" => Some(SortKey::Name),
+            "path" => Some(SortKey::Path),
+            "size" => Some(SortKey::Size),
+            "mtime" => Some(SortKey::Mtime),
+            "depth" => Some(SortKey::Depth),
+            "ext" => Some(SortKey::Ext),
+            _ => None,
+        }
+    }
+}
+
+// output settings, everything that decides which entries match lives in sf::SearchBuilder
 struct Config {
-    file_flag: bool,
-    dir_flag: bool,
-    no_hidden_flag: bool,
     performance_flag: bool,
     stats_flag: bool,
     stats_long_flag: bool,
     count_flag: bool,
-    depth_flag: u32,
-    pattern: String,
-    pattern_ac: AhoCorasick,
-    extensions: Vec<String>,
-    exclude_ac: AhoCorasick,
+    json_flag: bool,
+    print0_flag: bool,
     show_errors_flag: bool,
+    exec: Option<Exec>,
+    // None keeps the order of the walk
+    sort: Option<SortKey>,
+    reverse_flag: bool,
 }
 
 impl Config {
+    #[allow(clippy::too_many_arguments)]
     fn new(
-        file_flag: bool,
-        dir_flag: bool,
-        no_hidden_flag: bool,
         performance_flag: bool,
         stats_flag: bool,
         stats_long_flag: bool,
         count_flag: bool,
-        depth_flag: u32,
-        pattern: &Vec<&str>,
-        pattern_ac: AhoCorasick,
-        extensions: Vec<&String>,
-        exclude_ac: AhoCorasick,
+        json_flag: bool,
+        print0_flag: bool,
         show_errors_flag: bool,
+        exec: Option<Exec>,
+        sort: Option<SortKey>,
+        reverse_flag: bool,
     ) -> Self {
-        let pattern = pattern[0].to_string();
-        let extensions = extensions.into_iter().map(|e| e.to_string()).collect();
-
         Self {
-            file_flag,
-            dir_flag,
-            no_hidden_flag,
             performance_flag,
             stats_flag,
             stats_long_flag,
             count_flag,
-            depth_flag,
-            pattern,
-            pattern_ac,
-            extensions,
-            exclude_ac,
+            json_flag,
+            print0_flag,
             show_errors_flag,
+            exec,
+            sort,
+            reverse_flag,
         }
     }
 }
@@ -103,20 +151,57 @@ fn main() {
 
     // handle arguments
     let matches = sf().get_matches();
-    let mut file_flag = matches.get_flag("file");
-    let mut dir_flag = matches.get_flag("dir");
-    let mut no_hidden_flag = matches.get_flag("no-hidden");
-    let mut performance_flag = matches.get_flag("performance");
-    let mut stats_flag = matches.get_flag("stats");
-    let mut stats_long_flag = matches.get_flag("stats-long");
-    let mut count_flag = matches.get_flag("count");
-    let mut case_insensitive_flag = matches.get_flag("case-insensitive");
-    let mut show_errors_flag = matches.get_flag("show-errors");
     let override_flag = matches.get_flag("override");
 
+    // merge the config file beneath the arguments, unless the override flag is set
+    let settings = if override_flag {
+        Settings::cli_only(&matches)
+    } else {
+        let profile = matches.get_one::<String>("profile").map(|p| p.as_str());
+        Settings::load(&matches, &sf(), &config_dir, profile).unwrap_or_else(|err| {
+            error!("{err}");
+            process::exit(1);
+        })
+    };
+
+    let file_flag = settings.flag("file");
+    let dir_flag = settings.flag("dir");
+    let no_hidden_flag = settings.flag("no-hidden");
+    let no_ignore_flag = settings.flag("no-ignore");
+    let hidden_mode = match settings.value("hidden-mode").as_deref() {
+        Some("both") => HiddenMode::Both,
+        _ => HiddenMode::Native,
+    };
+    let performance_flag = settings.flag("performance");
+    let stats_flag = settings.flag("stats");
+    let stats_long_flag = settings.flag("stats-long");
+    let count_flag = settings.flag("count");
+    let json_flag = settings.flag("json");
+    let print0_flag = settings.flag("print0");
+    let null_input_flag = settings.flag("null-input");
+    let case_insensitive_flag = settings.flag("case-insensitive");
+    let smart_case_flag = settings.flag("smart-case") && !settings.flag("case-sensitive");
+    let regex_flag = settings.flag("regex");
+    let glob_flag = settings.flag("glob");
+    let fuzzy_flag = settings.flag("fuzzy");
+    let content_flag = settings.flag("content");
+    let exclude_regex_flag = settings.flag("exclude-regex");
+    let show_errors_flag = settings.flag("show-errors");
+    let follow_flag = settings.flag("follow");
+    let from_index_flag = settings.flag("from-index");
+    let interactive_flag = settings.flag("interactive");
+    let watch_flag = settings.flag("watch");
+    let reverse_flag = settings.flag("reverse");
+    let sort = settings.value("sort").map(|value| {
+        SortKey::from_value(&value).unwrap_or_else(|| {
+            error!("Unknown sort order \'{value}\'");
+            process::exit(1);
+        })
+    });
+
     // set default search depth
-    let mut depth_flag = 250;
-    if let Some(d) = matches.get_one::<String>("depth") {
+    let mut depth_flag = DEFAULT_DEPTH;
+    if let Some(d) = settings.value("depth") {
         match d.parse() {
             Ok(depth) => depth_flag = depth,
             Err(err) => {
@@ -126,18 +211,16 @@ fn main() {
         }
     }
 
-    // if override flag is set -> reset everything to default values
-    if override_flag {
-        file_flag = false;
-        dir_flag = false;
-        no_hidden_flag = false;
-        performance_flag = false;
-        stats_flag = false;
-        stats_long_flag = false;
-        count_flag = false;
-        depth_flag = 250;
-        case_insensitive_flag = false;
-        show_errors_flag = false;
+    // walk sequentially by default
+    let mut threads_flag = 1;
+    if let Some(t) = settings.value("threads") {
+        match t.parse() {
+            Ok(threads) => threads_flag = threads,
+            Err(err) => {
+                error!("Expected an integer for the number of threads: {err}");
+                process::exit(1);
+            }
+        }
     }
 
     if let Some(args) = matches
@@ -145,64 +228,194 @@ fn main() {
         .map(|a| a.collect::<Vec<_>>())
     {
         // get search pattern from arguments
-        let pattern = vec![args[0].as_str()];
-        // store search pattern in aho-corasick builder
-        // handle case-insensitive flag
-        let pattern_ac = AhoCorasickBuilder::new()
-            .ascii_case_insensitive(case_insensitive_flag)
-            .build(&pattern);
+        let pattern = args[0].as_str();
 
-        // get search path from arguments
-        let path = Path::new(&args[1]).to_path_buf();
+        // get search paths from arguments, '-' reads a list of paths from stdin
+        let paths = if args[1] == STDIN_PATH {
+            read_search_paths(null_input_flag).unwrap_or_else(|err| {
+                error!("Unable to read the search paths from stdin: {err}");
+                process::exit(1);
+            })
+        } else {
+            vec![Path::new(&args[1]).to_path_buf()]
+        };
 
         // get possible file extensions for filtering
-        let mut extensions = Vec::new();
-        if let Some(mut ext) = matches
-            .get_many::<String>("extension")
-            .map(|a| a.collect::<Vec<_>>())
-        {
-            extensions.append(&mut ext);
-        }
+        let extensions = settings.values("extension");
 
         // get exclude patterns
-        let mut exclude_patterns = Vec::new();
-        if let Some(mut excl) = matches
-            .get_many::<String>("exclude")
-            .map(|a| a.collect::<Vec<_>>())
-        {
-            exclude_patterns.append(&mut excl);
+        let exclude_patterns = settings.values("exclude");
+
+        // get type filters
+        let type_filters = settings
+            .values("type")
+            .iter()
+            .map(|kind| kind.parse::<EntryType>())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|err| {
+                error!("{err}");
+                process::exit(1);
+            });
+
+        // get size filters
+        let size_filters = settings
+            .values("size")
+            .iter()
+            .map(|size| size.parse::<SizeFilter>())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|err| {
+                error!("{err}");
+                process::exit(1);
+            });
+
+        // get time filters
+        let mut time_filters = Vec::new();
+        for (id, time, within) in [
+            ("changed-within", FileTime::Modified, true),
+            ("changed-before", FileTime::Modified, false),
+            ("accessed-within", FileTime::Accessed, true),
+            ("created-within", FileTime::Created, true),
+        ] {
+            if let Some(point) = settings.value(id) {
+                let filter = if within {
+                    TimeFilter::within(time, &point)
+                } else {
+                    TimeFilter::before(time, &point)
+                };
+                time_filters.push(filter.unwrap_or_else(|err| {
+                    error!("{err}");
+                    process::exit(1);
+                }));
+            }
+        }
+        if let Some(file) = settings.value("newer") {
+            time_filters.push(
+                TimeFilter::newer_than(Path::new(&file)).unwrap_or_else(|err| {
+                    error!("Unable to read the modification time of \'{file}\': {err}");
+                    process::exit(1);
+                }),
+            );
         }
 
-        // store exclude patterns in aho-corasick builder
-        // handle case-insensitive flag for exclude patterns
-        let exclude_ac = AhoCorasickBuilder::new()
-            .ascii_case_insensitive(case_insensitive_flag)
-            .build(&exclude_patterns);
+        // handle regex, glob and fuzzy flag
+        let match_mode = if regex_flag {
+            MatchMode::Regex
+        } else if glob_flag {
+            MatchMode::Glob
+        } else if fuzzy_flag {
+            MatchMode::Fuzzy
+        } else {
+            MatchMode::Literal
+        };
+
+        // read the index once for all search paths
+        let index = from_index_flag.then(|| {
+            Arc::new(open_index(&config_dir).unwrap_or_else(|err| {
+                error!("{err}");
+                process::exit(1);
+            }))
+        });
+
+        // path order comes straight from a sorted sequential walk or the index, everything else is sorted at the end
+        // ranked hits are collected anyway, they need the sort key to override the ranking
+        let sort_by_path = sort == Some(SortKey::Path)
+            && !reverse_flag
+            && match_mode != MatchMode::Fuzzy
+            && (threads_flag == 1 || index.is_some());
+        let sort = if sort_by_path { None } else { sort };
+
+        // build a searcher per search path
+        let mut searchers = paths
+            .into_iter()
+            .map(|path| {
+                let mut builder = SearchBuilder::new(pattern, path)
+                    .file_only(file_flag)
+                    .dir_only(dir_flag)
+                    .no_hidden(no_hidden_flag)
+                    .hidden_mode(hidden_mode)
+                    .ignore_files(!no_ignore_flag)
+                    .case_insensitive(case_insensitive_flag)
+                    .smart_case(smart_case_flag)
+                    .match_mode(match_mode)
+                    .exclude_regex(exclude_regex_flag)
+                    .content(content_flag)
+                    .depth(depth_flag)
+                    .threads(threads_flag)
+                    .sort_by_path(sort_by_path)
+                    .follow(follow_flag)
+                    .extensions(extensions.clone())
+                    .exclude(exclude_patterns.clone())
+                    .types(type_filters.clone())
+                    .sizes(size_filters.clone())
+                    .times(time_filters.clone())
+                    .count_bytes(stats_flag || stats_long_flag || json_flag);
+                if let Some(index) = &index {
+                    builder = builder.index(Arc::clone(index));
+                }
+
+                builder.build().unwrap_or_else(|err| {
+                    error!("{err}");
+                    process::exit(1);
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // get the command to run for the search results
+        // only from the command line, never from the config file
+        let exec = if let Some(command) = matches.get_many::<String>("exec") {
+            Some(Exec::new(command.cloned().collect(), ExecMode::PerHit))
+        } else {
+            matches
+                .get_many::<String>("exec-batch")
+                .map(|command| Exec::new(command.cloned().collect(), ExecMode::Batch))
+        };
 
         // construct Config
         let config = Config::new(
-            file_flag,
-            dir_flag,
-            no_hidden_flag,
             performance_flag,
             stats_flag,
             stats_long_flag,
             count_flag,
-            depth_flag,
-            &pattern,
-            pattern_ac,
-            extensions,
-            exclude_ac,
+            json_flag,
+            print0_flag,
             show_errors_flag,
+            exec,
+            sort,
+            reverse_flag,
         );
 
+        // pick from the search results instead of printing all of them
+        if interactive_flag {
+            let picked = interactive(&mut handle, searchers, &config);
+            handle
+                .flush()
+                .unwrap_or_else(|err| error!("Error flushing writer: {err}"));
+            if !picked {
+                process::exit(1);
+            }
+            return;
+        }
+
+        // remember the matches to report them once they are gone
+        let mut seen = watch_flag.then(BTreeSet::new);
+
         // start search
-        search(&mut handle, &path, &config);
+        let success = search(&mut handle, &mut searchers, &config, seen.as_mut());
 
         // empty bufwriter
         handle
             .flush()
             .unwrap_or_else(|err| error!("Error flushing writer: {err}"));
+
+        // only stops via ctrl-c
+        if let Some(seen) = seen {
+            watch(&mut handle, &searchers, &config, seen);
+        }
+
+        // some of the executed commands failed
+        if !success {
+            process::exit(1);
+        }
     } else {
         // handle commands
         match matches.subcommand() {
@@ -215,6 +428,119 @@ fn main() {
                     process::exit(1);
                 }
             }
+            Some(("index", index_matches)) => {
+                let index_path = config_dir.join(INDEX_FILE);
+                let start = Instant::now();
+
+                let (index, stats) = match index_matches.subcommand() {
+                    Some(("build", build_matches)) => {
+                        let path = build_matches
+                            .get_one::<String>("path")
+                            .expect("path is required");
+
+                        // add to the existing index, a broken one is replaced
+                        let mut index = match open_index(&config_dir) {
+                            Ok(index) => index,
+                            Err(IndexError::Io(err)) if err.kind() != io::ErrorKind::NotFound => {
+                                error!("Unable to read {}: {err}", index_path.display());
+                                process::exit(1);
+                            }
+                            Err(_) => Index::default(),
+                        };
+
+                        let pb = spinner("indexing");
+                        let stats = index.build(Path::new(path));
+                        pb.finish_and_clear();
+
+                        let stats = stats.unwrap_or_else(|err| {
+                            error!("Unable to index \'{path}\': {err}");
+                            process::exit(1);
+                        });
+                        (index, stats)
+                    }
+                    Some(("update", _)) => {
+                        let mut index = open_index(&config_dir).unwrap_or_else(|err| {
+                            error!("{err}");
+                            process::exit(1);
+                        });
+
+                        let pb = spinner("updating");
+                        let stats = index.update();
+                        pb.finish_and_clear();
+
+                        (index, stats)
+                    }
+                    _ => {
+                        unreachable!();
+                    }
+                };
+
+                index.save(&index_path).unwrap_or_else(|err| {
+                    error!("Unable to write {}: {err}", index_path.display());
+                    process::exit(1);
+                });
+                print_index_stats(&index, stats, start);
+            }
+            Some(("dupes", dupes_matches)) => {
+                let path = dupes_matches
+                    .get_one::<String>("path")
+                    .expect("path is required");
+                let pattern = dupes_matches
+                    .get_one::<String>("pattern")
+                    .expect("pattern has a default");
+
+                // the search filters, defaults from the config file apply as well
+                let extensions = dupes_matches
+                    .get_many::<String>("extension")
+                    .map(|values| values.cloned().collect())
+                    .unwrap_or_else(|| settings.values("extension"));
+                let exclude_patterns = dupes_matches
+                    .get_many::<String>("exclude")
+                    .map(|values| values.cloned().collect())
+                    .unwrap_or_else(|| settings.values("exclude"));
+                let no_hidden = dupes_matches.get_flag("no-hidden") || no_hidden_flag;
+                let depth = dupes_matches
+                    .get_one::<u32>("depth")
+                    .copied()
+                    .unwrap_or(depth_flag);
+
+                let start = Instant::now();
+                let searcher = SearchBuilder::new(pattern, path)
+                    .file_only(true)
+                    .no_hidden(no_hidden)
+                    .hidden_mode(hidden_mode)
+                    .ignore_files(!no_ignore_flag)
+                    .case_insensitive(case_insensitive_flag)
+                    .smart_case(smart_case_flag)
+                    .exclude_regex(exclude_regex_flag)
+                    .depth(depth)
+                    .threads(threads_flag)
+                    .extensions(extensions)
+                    .exclude(exclude_patterns)
+                    .count_bytes(true)
+                    .build()
+                    .unwrap_or_else(|err| {
+                        error!("{err}");
+                        process::exit(1);
+                    });
+
+                let pb = spinner("searching");
+                let files = searcher
+                    .flatten()
+                    .filter_map(|hit| Some((hit.path, hit.size?)))
+                    .collect::<Vec<_>>();
+                pb.finish_and_clear();
+
+                let file_count = files.len();
+                let pb = spinner("comparing");
+                let dupes = dupes::find(files);
+                pb.finish_and_clear();
+
+                print_dupes(&mut handle, &dupes, file_count, start);
+                handle
+                    .flush()
+                    .unwrap_or_else(|err| error!("Error flushing writer: {err}"));
+            }
             _ => {
                 unreachable!();
             }
@@ -238,16 +564,28 @@ fn sf() -> Command {
             "Leann Phydon <leann.phydon@gmail.com>".italic().dimmed()
         ))
         .long_about(format!(
-            "{}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n\n{}",
+            "{}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n  {}\n\n{}",
             "Simple file search",
             "- colourful output and search indicating spinner by default ",
-            "- filter by file, directory and file-extension",
+            "- filter by type, file-extension, size and time",
             "- exclude patterns from the search ",
-            "- exclude hidden files",
+            "- exclude hidden files (dotfiles on unix, hidden attribute on windows)",
+            "- respect .gitignore, .ignore and .sfignore files",
             "- show search statistics at the end",
+            "- sort the search results",
+            "- follow symlinks with loop detection",
             "- accepts \'.\' as current directory",
             "- search case insensitive",
-            "- no regex search",
+            "- search with regular expressions or globs",
+            "- fuzzy search with ranked results",
+            "- search in file contents",
+            "- JSON output for scripts",
+            "- NUL separated output and input for xargs",
+            "- run commands for the search results",
+            "- search a prebuilt index instead of the file system",
+            "- keep watching for new matches",
+            "- pick results in an interactive terminal ui",
+            "- find duplicate files",
             "Note: every set filter slows down the search".truecolor(250, 0, 104)
         ))
         // TODO update version
@@ -257,15 +595,87 @@ fn sf() -> Command {
         .arg(
             Arg::new("args")
                 .help("Add a search pattern and a path")
+                .long_help(format!(
+                    "{}\n{}",
+                    "Add a search pattern and a path",
+                    "Use '-' as path to read a list of search paths from stdin, one per line",
+                ))
                 .action(ArgAction::Set)
                 .num_args(2)
                 .value_names(["PATTERN", "PATH"]),
         )
+        .arg(
+            Arg::new("accessed-within")
+                .long("accessed-within")
+                .help("Only show entries accessed within the given time")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Only show entries accessed within the given time",
+                    "TIME is a duration back from now like 30min, 12h, 2d, 1w or a UTC date like 2024-01-01 or '2024-01-01 12:00'",
+                    "Many filesystems update access times only lazily (relatime)",
+                ))
+                .action(ArgAction::Set)
+                .value_name("TIME"),
+        )
         .arg(
             Arg::new("case-insensitive")
                 .short('i')
                 .long("case-insensitive")
                 .help("Search case insensitivly")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Search case insensitivly, applies to the exclude patterns as well",
+                    "Names with non-ASCII characters are compared with Unicode case folding, e.g. straße matches STRASSE and ss matches Straße",
+                    "The folding is lowercasing plus the common special cases like ß and ﬁ, not the complete Unicode table",
+                ))
+                .conflicts_with_all(["case-sensitive", "smart-case"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("case-sensitive")
+                .long("case-sensitive")
+                .help("Search case sensitively, e.g. to override smart-case from the config file")
+                .conflicts_with("smart-case")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("changed-before")
+                .long("changed-before")
+                .help("Only show entries last modified before the given time")
+                .long_help(format!(
+                    "{}\n{}",
+                    "Only show entries last modified before the given time, e.g. stale build artifacts",
+                    "TIME is a duration back from now like 30min, 12h, 2d, 1w or a UTC date like 2024-01-01 or '2024-01-01 12:00'",
+                ))
+                .action(ArgAction::Set)
+                .value_name("TIME"),
+        )
+        .arg(
+            Arg::new("changed-within")
+                .long("changed-within")
+                .help("Only show entries modified within the given time")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Only show entries modified within the given time",
+                    "TIME is a duration back from now like 30min, 12h, 2d, 1w or a UTC date like 2024-01-01 or '2024-01-01 12:00'",
+                    "E.g. sf '' . --changed-within 2d",
+                ))
+                .action(ArgAction::Set)
+                .value_name("TIME"),
+        )
+        .arg(
+            Arg::new("content")
+                .short('C')
+                .long("content")
+                .help("Search for the pattern in file contents instead of names")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}",
+                    "Search for the pattern in file contents instead of names",
+                    "Prints every matching line with its line number",
+                    "The --extension, --exclude and --depth filters still apply, exclude patterns are matched against the names",
+                    "Binary files are skipped",
+                ))
+                .conflicts_with_all(["dir", "glob", "fuzzy"])
                 .action(ArgAction::SetTrue),
         )
         .arg(
@@ -279,6 +689,19 @@ fn sf() -> Command {
                 ))
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new("created-within")
+                .long("created-within")
+                .help("Only show entries created within the given time")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Only show entries created within the given time",
+                    "TIME is a duration back from now like 30min, 12h, 2d, 1w or a UTC date like 2024-01-01 or '2024-01-01 12:00'",
+                    "Entries without a creation time (not supported by every filesystem) never match",
+                ))
+                .action(ArgAction::Set)
+                .value_name("TIME"),
+        )
         .arg(
             Arg::new("depth")
                 .short('D')
@@ -297,6 +720,54 @@ fn sf() -> Command {
                 .action(ArgAction::SetTrue)
                 .conflicts_with("file"),
         )
+        .arg(
+            Arg::new("exclude-regex")
+                .long("exclude-regex")
+                .help("Treat the exclude patterns as regular expressions")
+                .requires("exclude")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("exec")
+                .short('x')
+                .long("exec")
+                .help("Run a command for every search result")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}\n{}\n{}",
+                    "Run a command for every search result instead of printing it, the commands run in parallel",
+                    "Placeholders: {} path, {//} parent, {/} basename, {/.} basename without extension",
+                    "The path is appended if the command has no placeholder",
+                    "All following arguments belong to the command, end it with ';' to add more flags",
+                    "Exits with an error if any of the commands failed",
+                    "E.g. sf '' . -e md -x wc -l {}",
+                ))
+                .num_args(1..)
+                .allow_hyphen_values(true)
+                .value_terminator(";")
+                .value_name("CMD")
+                .conflicts_with_all(["exec-batch", "json", "print0"])
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("exec-batch")
+                .short('X')
+                .long("exec-batch")
+                .help("Run a command once with all search results")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}\n{}",
+                    "Run a command once with all search results as arguments instead of printing them",
+                    "Arguments with a placeholder are repeated for every result, placeholders as for --exec",
+                    "Too many results are split into several commands to stay below the argument limit",
+                    "All following arguments belong to the command, end it with ';' to add more flags",
+                    "E.g. sf '' . -e log -X rm",
+                ))
+                .num_args(1..)
+                .allow_hyphen_values(true)
+                .value_terminator(";")
+                .value_name("CMD")
+                .conflicts_with_all(["json", "print0"])
+                .action(ArgAction::Set),
+        )
         .arg(
             Arg::new("extension")
                 .short('e')
@@ -333,6 +804,119 @@ fn sf() -> Command {
                 .help("Search only in file names for the pattern")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("hidden-mode")
+                .long("hidden-mode")
+                .help("Set what counts as hidden for the --no-hidden flag")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Set what counts as hidden for the --no-hidden flag",
+                    "native: dotfiles on unix, entries with the hidden attribute on windows",
+                    "both: dotfiles and entries with the hidden attribute on every platform",
+                ))
+                .value_parser(["native", "both"])
+                .default_value("native")
+                .action(ArgAction::Set)
+                .num_args(1)
+                .value_name("MODE"),
+        )
+        .arg(
+            Arg::new("follow")
+                .short('F')
+                .long("follow")
+                .help("Follow symlinks")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Follow symlinks and search the content of linked directories",
+                    "Hits reached via a symlink are marked, links pointing to one of their ancestors are skipped",
+                    "Symlink loops and broken links are reported via the --show-errors flag",
+                ))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("from-index")
+                .long("from-index")
+                .help("Search the prebuilt index instead of the file system")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}",
+                    "Search the prebuilt index instead of walking the file system, build it via sf index build",
+                    "The search path has to lie within an indexed directory",
+                    "Only matching entries are read from disk, entries removed since the last update are skipped",
+                    "New entries are only found after sf index update",
+                ))
+                .conflicts_with("follow")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("fuzzy")
+                .short('z')
+                .long("fuzzy")
+                .help("Match the characters of the search pattern in order, rank the results")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Match the characters of the search pattern in order, but not necessarily next to each other",
+                    "Results are ranked: matches at word boundaries, consecutive characters and shorter names come first",
+                    "Results are printed once the search is finished",
+                ))
+                .conflicts_with_all(["regex", "glob"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("glob")
+                .short('g')
+                .long("glob")
+                .help("Treat the search pattern as a glob")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Treat the search pattern as a glob, e.g. '*.test.*'",
+                    "The glob has to match the whole name",
+                    "If the glob contains a '/' it is matched against the path relative to the search path, '**' matches any number of directories",
+                ))
+                .conflicts_with("regex")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .help("Pick search results in a terminal ui")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}",
+                    "Pick search results in a terminal ui, results show up while the search is still running",
+                    "Type to narrow the results down, every word has to be in the path (case insensitive unless it has an uppercase character)",
+                    "Up/Down or Ctrl-P/Ctrl-N to move, Tab to select multiple results, Enter to print the selection, Ctrl-O to open it, Esc to cancel",
+                    "E.g. vim \"$(sf main . -e rs --interactive)\"",
+                ))
+                .conflicts_with_all(["count", "stats", "stats-long", "json", "exec", "exec-batch", "watch"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("json")
+                .short('j')
+                .long("json")
+                .help("Print the search results as JSON, one object per line")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}\n{}",
+                    "Print the search results as JSON, one object per line (NDJSON)",
+                    "Every result has the fields path, name, parent, type, size and mtime (seconds since the unix epoch)",
+                    "The last line is a stats object: {\"stats\": {\"entries\", \"dirs\", \"errors\", \"matches\", \"matched_bytes\", \"duration_ms\", \"entries_per_sec\", ...}}",
+                    "It also holds the matches per type and extension, the largest and the deepest match and the errors per kind",
+                    "Can be combined with the --count flag to only print the stats object",
+                ))
+                .conflicts_with_all(["stats", "stats-long"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("newer")
+                .long("newer")
+                .help("Only show entries modified after the given file")
+                .long_help(format!(
+                    "{}\n{}",
+                    "Only show entries modified after the given file was modified",
+                    "E.g. sf '' src --newer target/release/sf",
+                ))
+                .action(ArgAction::Set)
+                .value_name("FILE"),
+        )
         .arg(
             Arg::new("no-hidden")
                 .short('H')
@@ -345,19 +929,44 @@ fn sf() -> Command {
                 ))
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("no-ignore")
+                .short('I')
+                .long("no-ignore")
+                .help("Don`t respect .gitignore, .ignore and .sfignore files")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}",
+                    "Don`t respect .gitignore, .ignore and .sfignore files",
+                    "By default entries listed in these files and in the global git excludes are skipped, ignored directories are not entered",
+                    "Ignore files above the search path are respected up to the root of the git repository",
+                    "Also searches in .git directories",
+                ))
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("override")
                 .short('o')
                 .long("override")
-                .help("Override all previously set flags")
+                .help("Ignore the config file")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Ignore the config file",
+                    "Only the flags given on the command line are used",
+                    "Cannot be set together with the --profile flag",
+                ))
+                .conflicts_with("profile")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("null-input")
+                .long("null-input")
+                .help("Read the search paths from stdin separated by NUL instead of newlines")
                 .long_help(format!(
                     "{}\n{}\n{}",
-                    "Override all previously set flags",
-                    "This can be used when a custom alias for this command is set together with regularly used flags",
-                    "This flag allows to disable these flags and specify new ones"
+                    "Read the search paths from stdin separated by NUL instead of newlines",
+                    "Only used if the search path is '-', e.g. find . -maxdepth 1 -type d -print0 | sf main - --null-input",
+                    "Safe for paths containing spaces or newlines",
                 ))
-                // TODO if new args -> add here to this list to override if needed
-                .overrides_with_all(["stats", "stats-long", "file", "dir", "extension", "exclude", "no-hidden", "performance", "count", "show-errors"])
                 .action(ArgAction::SetTrue),
         )
         .arg(
@@ -375,6 +984,57 @@ fn sf() -> Command {
                 .action(ArgAction::SetTrue)
                 .conflicts_with_all(["stats", "stats-long"]),
         )
+        .arg(
+            Arg::new("print0")
+                .short('0')
+                .long("print0")
+                .help("Separate the search results by NUL instead of newlines")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}",
+                    "Separate the search results by NUL instead of newlines, e.g. for xargs -0",
+                    "Only prints the paths without any decoration, files with content matches are printed once",
+                    "Disables the search indicating spinner like the --performance flag",
+                    "Cannot be set together with the --json or --stats flag",
+                ))
+                .conflicts_with_all(["json", "stats", "stats-long"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("profile")
+                .short('P')
+                .long("profile")
+                .help("Use a profile from the config file")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Use a profile from the config file, e.g. [profiles.code] via --profile code",
+                    "The profile is merged over the [default] table, flags on the command line take precedence",
+                    "The config file is located at ~/.config/sf/config.toml (Windows: %APPDATA%\\sf\\config.toml)",
+                ))
+                .action(ArgAction::Set)
+                .num_args(1)
+                .value_name("NAME"),
+        )
+        .arg(
+            Arg::new("regex")
+                .short('r')
+                .long("regex")
+                .help("Treat the search pattern as a regular expression")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Treat the search pattern as a regular expression",
+                    "The pattern can match anywhere in the name, use ^ and $ to anchor it",
+                    "Use --exclude-regex to treat the exclude patterns as regular expressions as well",
+                ))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("reverse")
+                .short('R')
+                .long("reverse")
+                .help("Reverse the order of the --sort flag")
+                .requires("sort")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("show-errors")
                 .long("show-errors")
@@ -386,6 +1046,51 @@ fn sf() -> Command {
                 ))
                 .action(ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new("smart-case")
+                .long("smart-case")
+                .help("Search case insensitively if the pattern is all lowercase")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Search case insensitively if the pattern is all lowercase, case sensitively otherwise",
+                    "Applies to the exclude patterns as well, escapes like \\D don't count as uppercase in regex mode",
+                    "Can be set as default in the config file: smart-case = true",
+                ))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("size")
+                .short('S')
+                .long("size")
+                .help("Only search in files with the given size")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}\n{}",
+                    "Only search in files with the given size, all given filters must match",
+                    "+SIZE at least, -SIZE at most, SIZE exactly, MIN..MAX in between (either end can be left out)",
+                    "Units: b, k, m, g, t (powers of 1024), e.g. --size +100M or --size 1k..4k",
+                    "Directories never match a size filter",
+                    "Can be given multiple times, e.g. --size +1k --size -1M"
+                ))
+                .action(ArgAction::Append)
+                .allow_hyphen_values(true)
+                .conflicts_with("dir")
+                .value_name("SIZE"),
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .help("Sort the search results")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}",
+                    "Sort the search results in ascending order, e.g. smallest or oldest first",
+                    "Ties are sorted by path, so the output is the same on every machine",
+                    "Path order is streamed for a sequential search, all other orders are printed once the search is finished",
+                    "Overrides the ranking of the --fuzzy flag",
+                ))
+                .value_parser(["name", "path", "size", "mtime", "depth", "ext"])
+                .action(ArgAction::Set)
+                .value_name("ORDER"),
+        )
         .arg(
             Arg::new("stats")
                 .short('s')
@@ -405,267 +1110,906 @@ fn sf() -> Command {
                 .long("stats-long")
                 .help("Show search statistics at the end")
                 .long_help(format!(
-                    "{}\n{}\n{}",
+                    "{}\n{}\n{}\n{}",
                     "Show search statistics at the end",
+                    "Besides the numbers of --stats: directories visited, errors per kind, matches per type and extension, the largest and the deepest match and entries per second",
                     "Can be combined with the --count flag to only show stats",
                     "Cannot be set together with the --performance flag",
                 ))
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("threads")
+                .short('t')
+                .long("threads")
+                .help("Set the number of threads to search with")
+                .long_help(format!(
+                    "{}\n{}\n{}",
+                    "Set the number of threads to search with",
+                    "0 uses one thread per available core",
+                    "With more than one thread the results come in no particular order",
+                ))
+                .default_value("1")
+                .action(ArgAction::Set)
+                .num_args(1)
+                .value_name("NUMBER"),
+        )
+        .arg(
+            Arg::new("type")
+                .short('T')
+                .long("type")
+                .help("Only search for entries of the given type")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}\n{}",
+                    "Only search for entries of the given type, entries matching any of the given types are shown",
+                    "Short forms: f, d, l, x, e, s, p, b, c and B for broken-symlink",
+                    "Symlinks are not followed, so a symlink to a directory is no dir",
+                    "Can be given multiple times and combined with the --file, --dir and --extension flag",
+                    "E.g. sf '' . --type empty --type broken-symlink",
+                ))
+                .action(ArgAction::Append)
+                .value_parser(
+                    EntryType::NAMES.map(|(name, alias)| PossibleValue::new(name).alias(alias)),
+                )
+                .value_name("TYPE"),
+        )
+        .arg(
+            Arg::new("watch")
+                .short('w')
+                .long("watch")
+                .help("Keep watching the search path and print entries that start or stop matching")
+                .long_help(format!(
+                    "{}\n{}\n{}\n{}",
+                    "Keep watching the search path after the search and print entries that start or stop matching, until Ctrl-C",
+                    "Entries created or renamed that pass all filters are printed, matches deleted or renamed are marked as removed",
+                    "Commands via --exec run for the new matches as well, --print0 only prints new matches",
+                    "Uses inotify on linux",
+                ))
+                .conflicts_with_all(["count", "stats", "stats-long", "exec-batch"])
+                .action(ArgAction::SetTrue),
+        )
         .subcommand(
             Command::new("log")
                 .short_flag('L')
                 .long_flag("log")
                 .about("Show content of the log file"),
         )
+        .subcommand(
+            Command::new("index")
+                .about("Build or update the index for searches via --from-index")
+                .long_about(format!(
+                    "{}\n{}\n{}",
+                    "Build or update the index for searches via --from-index",
+                    "The index holds the names of all entries below the indexed directories",
+                    "It is stored in the config directory",
+                ))
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("build")
+                        .about("Index every entry below the given directory")
+                        .long_about(format!(
+                            "{}\n{}",
+                            "Index every entry below the given directory, replaces what was indexed at or below it",
+                            "Hidden and ignored entries are indexed as well, the search flags decide whether they are shown",
+                        ))
+                        .arg(
+                            Arg::new("path")
+                                .help("Directory to index")
+                                .required(true)
+                                .value_name("PATH"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("update")
+                        .about("Only read the directories modified since the last build or update again"),
+                ),
+        )
+        .subcommand(
+            Command::new("dupes")
+                .about("Find duplicate files")
+                .long_about(format!(
+                    "{}\n{}\n{}",
+                    "Find files with the same content below the given directory",
+                    "Files are compared by size, then by their first 4 KB, then by their whole content",
+                    "Empty files and hard links to the same file are no duplicates",
+                ))
+                .arg(
+                    Arg::new("path")
+                        .help("Directory to search for duplicates")
+                        .required(true)
+                        .value_name("PATH"),
+                )
+                .arg(
+                    Arg::new("pattern")
+                        .help("Only compare files containing the pattern in their name")
+                        .default_value("")
+                        .hide_default_value(true)
+                        .value_name("PATTERN"),
+                )
+                .arg(
+                    Arg::new("depth")
+                        .short('D')
+                        .long("depth")
+                        .help("Set max search depth")
+                        .action(ArgAction::Set)
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(u32))
+                        .value_name("NUMBER"),
+                )
+                .arg(
+                    Arg::new("extension")
+                        .short('e')
+                        .long("extension")
+                        .help("Only compare files with the given extensions")
+                        .action(ArgAction::Set)
+                        .num_args(1..)
+                        .value_name("EXTENSIONS"),
+                )
+                .arg(
+                    Arg::new("exclude")
+                        .short('E')
+                        .long("exclude")
+                        .help("Enter patterns to exclude from the search")
+                        .action(ArgAction::Set)
+                        .num_args(1..)
+                        .value_name("PATTERNS"),
+                )
+                .arg(
+                    Arg::new("no-hidden")
+                        .short('H')
+                        .long("no-hidden")
+                        .help("Exclude hidden files and directories from search")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
 }
 
-fn search<W: Write>(handle: &mut W, path: &PathBuf, config: &Config) {
+// returns false if any executed command failed
+fn search<W: Write>(
+    handle: &mut W,
+    searchers: &mut [Searcher],
+    config: &Config,
+    seen: Option<&mut BTreeSet<PathBuf>>,
+) -> bool {
     let start = Instant::now();
-    let mut entry_count = 0;
-    let mut error_count = 0;
-    let mut search_hits = 0;
+
+    // run commands instead of printing the search results
+    let mut runner = config.exec.as_ref().map(Runner::start);
+
+    // the details of the results are only shown in the long and the JSON stats
+    let mut report = (config.stats_long_flag || config.json_flag).then(Report::default);
 
     // disable the search indicating spinner and colourful output
     // write to bufwriter
-    if config.performance_flag {
+    // executed commands write to stdout as well
+    if config.performance_flag || config.json_flag || config.print0_flag || runner.is_some() {
         forwards_search(
             handle,
-            path,
-            &config,
-            &mut search_hits,
-            &mut entry_count,
-            &mut error_count,
+            searchers,
+            config,
+            &mut runner,
             None,
+            seen,
+            report.as_mut(),
         );
     } else {
-        // spinner
-        let spinner_style = ProgressStyle::with_template("{spinner:.red} {msg}").unwrap();
-        let pb = ProgressBar::new_spinner();
-        pb.enable_steady_tick(Duration::from_millis(120));
-        pb.set_style(spinner_style);
-        pb.set_message(format!("{}", "searching".truecolor(250, 0, 104)));
+        let pb = spinner("searching");
 
         forwards_search(
             handle,
-            path,
-            &config,
-            &mut search_hits,
-            &mut entry_count,
-            &mut error_count,
+            searchers,
+            config,
+            &mut runner,
             Some(pb.clone()),
+            seen,
+            report.as_mut(),
         );
 
         pb.finish_and_clear();
     }
 
+    // sum up the stats of all search paths
+    // wait for the executed commands before printing the stats
+    let exec_status = runner.map(Runner::finish);
+
+    let mut stats = Stats::default();
+    for searcher_stats in searchers.iter().map(Searcher::stats) {
+        stats.search_hits += searcher_stats.search_hits;
+        stats.entry_count += searcher_stats.entry_count;
+        stats.dir_count += searcher_stats.dir_count;
+        stats.error_count += searcher_stats.error_count;
+        stats.matched_bytes += searcher_stats.matched_bytes;
+    }
+
     // print output >> stats or count
-    if config.count_flag && config.stats_flag || config.stats_flag && !config.count_flag {
-        get_search_hits_short(search_hits, entry_count, error_count, start);
-    } else if config.count_flag && config.stats_long_flag
-        || config.stats_long_flag && !config.count_flag
-    {
-        get_search_hits_long(search_hits, entry_count, error_count, start);
-    } else if config.count_flag && !config.stats_flag
-        || config.count_flag && !config.stats_long_flag
-    {
-        println!("{}", search_hits.to_string());
+    // stats win over count, count only hides the search results then
+    let report = report.unwrap_or_default();
+    if config.json_flag {
+        write_json_stats(handle, &stats, &report, start);
+    } else if config.stats_flag {
+        get_search_hits_short(&stats, start);
+    } else if config.stats_long_flag {
+        get_search_hits_long(&stats, &report, start);
+    } else if config.count_flag {
+        println!("{}", stats.search_hits);
+    }
+
+    match exec_status {
+        Some(status) => report_exec_status(status),
+        None => true,
     }
 }
 
 fn forwards_search<W: Write>(
     handle: &mut W,
-    path: &PathBuf,
+    searchers: &mut [Searcher],
     config: &Config,
-    search_hits: &mut u64,
-    entry_count: &mut u64,
-    error_count: &mut u64,
+    runner: &mut Option<Runner>,
     pb: Option<ProgressBar>,
+    mut seen: Option<&mut BTreeSet<PathBuf>>,
+    mut report: Option<&mut Report>,
 ) {
-    let mut search_path = Path::new(&path).to_path_buf();
+    // ranked and sorted hits can only be printed once all of them are known
+    // the index of the searcher is kept to highlight the hit later
+    let mut ranked_hits = Vec::new();
 
-    // accept "." as current directory
-    if path.as_path().to_string_lossy().to_string() == "." {
-        let current_dir = env::current_dir().unwrap_or_else(|err| {
-            error!("Unable to get current directory: {err}");
-            process::exit(1);
-        });
-        search_path.push(current_dir);
-    }
-
-    // filter files
-    let valid_entries = WalkDir::new(search_path)
-        .max_depth(config.depth_flag as usize) // set maximum search depth
-        .into_iter()
-        // TODO bottleneck if it has to filter out hidden files
-        .filter_entry(|e| file_check(e, &config)); // handle hidden flag
-
-    for entry in valid_entries {
-        match entry {
-            Ok(entry) => {
-                // handle file flag
-                // must be outside of function file_check()
-                // else no file will be searched with WalkDir...filter_entry()
-                if config.file_flag && !entry.file_type().is_file() {
-                    continue;
-                }
+    for (index, searcher) in searchers.iter_mut().enumerate() {
+        let collect = searcher.is_ranked() || config.sort.is_some();
+        while let Some(hit) = searcher.next() {
+            match hit {
+                Ok(hit) => {
+                    if let Some(seen) = seen.as_deref_mut() {
+                        seen.insert(hit.path.clone());
+                    }
+                    if let Some(report) = report.as_deref_mut() {
+                        report.add_hit(&hit);
+                    }
 
-                // handle dir flag
-                // must be outside of function file_check()
-                // else search stops if dir is found via WalkDir...filter_entry()
-                if config.dir_flag && !entry.file_type().is_dir() {
-                    continue;
+                    if collect {
+                        ranked_hits.push((index, hit));
+                    } else {
+                        match runner {
+                            Some(runner) => runner.push(hit.path),
+                            None => {
+                                match_pattern_and_print(handle, &hit, searcher, config, pb.clone())
+                            }
+                        }
+                    }
                 }
-
-                // count searched entries
-                *entry_count += 1;
-
-                // get filename
-                let mut name = String::new();
-                name.push_str(&entry.file_name().to_string_lossy().to_string());
-
-                // get parent path
-                let parent = entry
-                    .path()
-                    .parent()
-                    .unwrap_or_else(|| Path::new(""))
-                    .to_string_lossy()
-                    .to_string()
-                    .replace("\\", "/");
-
-                // handle possible file extensions
-                if !config.extensions.is_empty() {
-                    // get entry extension
-                    let mut entry_extension = String::new();
-                    if let Some(extension) = entry.path().extension() {
-                        entry_extension.push_str(&extension.to_string_lossy().to_string());
-
-                        // check if entry_extension matches any given extension via extensions flag
-                        if config.extensions.iter().any(|it| &entry_extension == it) {
-                            match_pattern_and_print(
-                                handle,
-                                name,
-                                parent,
-                                &config,
-                                pb.clone(),
-                                search_hits,
-                            );
+                Err(err) => {
+                    if let Some(report) = report.as_deref_mut() {
+                        report.add_error(&err);
+                    }
+                    if config.show_errors_flag {
+                        if let Some(progbar) = pb.clone() {
+                            progbar.suspend(|| report_error(&err));
+                        } else {
+                            report_error(&err);
                         }
                     }
-                } else {
-                    match_pattern_and_print(handle, name, parent, &config, pb.clone(), search_hits);
                 }
             }
-            Err(err) => {
-                // count errors
-                *error_count += 1;
+        }
+    }
 
-                if config.show_errors_flag {
-                    let path = err.path().unwrap_or(Path::new("")).display();
-                    if let Some(inner) = err.io_error() {
-                        if let Some(progbar) = pb.clone() {
-                            progbar.suspend(|| {
-                                match inner.kind() {
-                                    io::ErrorKind::InvalidData => {
-                                        warn!("Entry \'{}\' contains invalid data: {}", path, inner)
-                                    }
-                                    io::ErrorKind::NotFound => {
-                                        warn!("Entry \'{}\' not found: {}", path, inner);
-                                    }
-                                    io::ErrorKind::PermissionDenied => {
-                                        warn!("Missing permission to read entry \'{}\': {}", path, inner)
-                                    }
-                                    _ => {
-                                        error!(
-                                            "Failed to access entry: \'{}\'\nUnexpected error occurred: {}",
-                                            path, inner
-                                        )
-                                    }
-                                }
-                            });
-                        } else {
-                            match inner.kind() {
-                                io::ErrorKind::InvalidData => {
-                                    warn!("Entry \'{}\' contains invalid data: {}", path, inner)
-                                }
-                                io::ErrorKind::NotFound => {
-                                    warn!("Entry \'{}\' not found: {}", path, inner);
-                                }
-                                io::ErrorKind::PermissionDenied => {
-                                    warn!(
-                                        "Missing permission to read entry \'{}\': {}",
-                                        path, inner
-                                    )
-                                }
-                                _ => {
-                                    error!(
-                                        "Failed to access entry: \'{}\'\nUnexpected error occurred: {}",
-                                        path, inner
-                                    )
+    order_hits(&mut ranked_hits, config, |(_, hit)| hit);
+
+    for (index, hit) in ranked_hits {
+        match runner {
+            Some(runner) => runner.push(hit.path),
+            None => match_pattern_and_print(handle, &hit, &searchers[index], config, pb.clone()),
+        }
+    }
+}
+
+// report entries that start or stop matching, until ctrl-c
+fn watch<W: Write>(
+    handle: &mut W,
+    searchers: &[Searcher],
+    config: &Config,
+    mut seen: BTreeSet<PathBuf>,
+) {
+    // the watcher reports absolute paths, also for relative search paths
+    let roots = searchers
+        .iter()
+        .map(|searcher| {
+            path::absolute(searcher.path()).unwrap_or_else(|_| searcher.path().to_path_buf())
+        })
+        .collect::<Vec<_>>();
+    let paths = roots.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+    let changes = Watch::new(&paths).unwrap_or_else(|err| {
+        error!("Unable to watch the search path: {err}");
+        process::exit(1);
+    });
+
+    // per hit commands run for new matches as well
+    let mut runner = config.exec.as_ref().map(Runner::start);
+
+    // keep the spinner while watching, new matches are printed above it
+    let pb =
+        (!config.performance_flag && !config.json_flag && !config.print0_flag && runner.is_none())
+            .then(|| spinner("watching"));
+
+    for change in changes {
+        match change {
+            Ok(Change::Created(path)) => {
+                // the deepest search path containing the entry decides
+                let Some((searcher, path)) = searchers
+                    .iter()
+                    .zip(&roots)
+                    .filter(|(_, root)| path.starts_with(root))
+                    .max_by_key(|(_, root)| root.components().count())
+                    .and_then(|(searcher, _)| Some((searcher, searcher.walk_path(&path)?)))
+                else {
+                    continue;
+                };
+
+                // a directory moved here comes without events for its content
+                for entry in WalkDir::new(&path).into_iter().flatten() {
+                    match searcher.check_path(entry.path()) {
+                        // reported already if it was created right after its directory
+                        Ok(Some(hit)) if seen.insert(hit.path.clone()) => match &mut runner {
+                            Some(runner) => runner.push(hit.path),
+                            None => {
+                                match_pattern_and_print(handle, &hit, searcher, config, pb.clone())
+                            }
+                        },
+                        Ok(_) => {}
+                        Err(err) => {
+                            if config.show_errors_flag {
+                                match &pb {
+                                    Some(pb) => pb.suspend(|| report_error(&err)),
+                                    None => report_error(&err),
                                 }
                             }
                         }
                     }
                 }
             }
+            Ok(Change::Removed(path)) => {
+                // a removed directory takes the matches below it along
+                // hits are stored like the walk of their search path reported them
+                let removed = searchers
+                    .iter()
+                    .filter_map(|searcher| searcher.walk_path(&path))
+                    .flat_map(|path| {
+                        seen.range(path.clone()..)
+                            .take_while(|it| it.starts_with(&path))
+                            .cloned()
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<BTreeSet<_>>();
+                for path in removed {
+                    seen.remove(&path);
+                    if runner.is_none() {
+                        print_removed(handle, &path, config, pb.as_ref());
+                    }
+                }
+            }
+            Err(err) => {
+                if config.show_errors_flag {
+                    match &pb {
+                        Some(pb) => pb.suspend(|| error!("{err}")),
+                        None => error!("{err}"),
+                    }
+                }
+            }
+        }
+
+        // show every change right away
+        handle
+            .flush()
+            .unwrap_or_else(|err| error!("Error flushing writer: {err}"));
+    }
+}
+
+// pick from the results while the search is still running, false if nothing was picked or opened
+fn interactive<W: Write>(handle: &mut W, searchers: Vec<Searcher>, config: &Config) -> bool {
+    let ordered = config.sort.is_some() || searchers.iter().any(Searcher::is_ranked);
+
+    let (sender, results) = mpsc::channel();
+    thread::spawn(move || {
+        for mut searcher in searchers {
+            while let Some(hit) = searcher.next() {
+                // errors would garble the ui
+                let Ok(hit) = hit else {
+                    continue;
+                };
+                let spans = searcher.match_spans(&hit.name);
+                // the picker was closed before the search finished
+                if sender.send(Item { hit, spans }).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    let outcome = interactive::pick(results, |items| {
+        if ordered {
+            order_hits(items, config, |item| &item.hit);
+        }
+    })
+    .unwrap_or_else(|err| {
+        error!("Unable to start the interactive mode: {err}");
+        process::exit(1);
+    });
+
+    match outcome {
+        Outcome::Print(paths) => {
+            for path in &paths {
+                let result = if config.print0_flag {
+                    write_path0(handle, path)
+                } else {
+                    writeln!(handle, "{}", path.display())
+                };
+                result.unwrap_or_else(|err| error!("Error writing to stdout: {err}"));
+            }
+            !paths.is_empty()
+        }
+        Outcome::Open(paths) => {
+            let mut success = !paths.is_empty();
+            for path in &paths {
+                if let Err(err) = interactive::open(path) {
+                    error!("Unable to open \'{}\': {err}", path.display());
+                    success = false;
+                }
+            }
+            success
+        }
+        Outcome::Abort => false,
+    }
+}
+
+// the order of --sort and --reverse, best score first otherwise
+fn order_hits<T>(hits: &mut [T], config: &Config, hit: fn(&T) -> &Hit) {
+    match config.sort {
+        Some(sort) => sort_hits(hits, sort, hit),
+        // ties in path order to keep the output stable
+        None => hits.sort_by(|a, b| {
+            let (a, b) = (hit(a), hit(b));
+            b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path))
+        }),
+    }
+    if config.reverse_flag {
+        hits.reverse();
+    }
+}
+
+// ascending, ties in path order to keep the output stable
+// metadata is read once per hit, missing metadata sorts first
+fn sort_hits<T>(hits: &mut [T], sort: SortKey, hit: fn(&T) -> &Hit) {
+    match sort {
+        SortKey::Name => hits.sort_by(|a, b| {
+            let (a, b) = (hit(a), hit(b));
+            a.name.cmp(&b.name).then_with(|| a.path.cmp(&b.path))
+        }),
+        SortKey::Path => hits.sort_by(|a, b| {
+            let (a, b) = (hit(a), hit(b));
+            a.path.cmp(&b.path)
+        }),
+        SortKey::Size => hits.sort_by_cached_key(|it| {
+            let hit = hit(it);
+            let size = hit
+                .size
+                .or_else(|| hit_metadata(hit).map(|metadata| metadata.len()));
+            (size, hit.path.clone())
+        }),
+        SortKey::Mtime => hits.sort_by_cached_key(|it| {
+            let hit = hit(it);
+            let mtime = hit_metadata(hit).and_then(|metadata| metadata.modified().ok());
+            (mtime, hit.path.clone())
+        }),
+        SortKey::Depth => hits.sort_by(|a, b| {
+            let (a, b) = (hit(a), hit(b));
+            a.depth.cmp(&b.depth).then_with(|| a.path.cmp(&b.path))
+        }),
+        SortKey::Ext => hits.sort_by(|a, b| {
+            let (a, b) = (hit(a), hit(b));
+            a.path
+                .extension()
+                .cmp(&b.path.extension())
+                .then_with(|| a.path.cmp(&b.path))
+        }),
+    }
+}
+
+// metadata of the link target for hits reached via --follow and for a symlinked search path
+fn hit_metadata(hit: &Hit) -> Option<fs::Metadata> {
+    if hit.via_link || hit.depth == 0 {
+        if let Ok(metadata) = fs::metadata(&hit.path) {
+            return Some(metadata);
+        }
+    }
+    fs::symlink_metadata(&hit.path).ok()
+}
+
+// search paths separated by newlines, or by NUL with --null-input
+// empty entries are skipped
+fn read_search_paths(null_input: bool) -> io::Result<Vec<PathBuf>> {
+    let separator = if null_input { b'\0' } else { b'\n' };
+
+    let mut paths = Vec::new();
+    for entry in io::stdin().lock().split(separator) {
+        let mut entry = entry?;
+        if !null_input && entry.ends_with(b"\r") {
+            entry.pop();
+        }
+        if !entry.is_empty() {
+            paths.push(bytes_to_path(entry));
+        }
+    }
+
+    Ok(paths)
+}
+
+#[cfg(unix)]
+fn bytes_to_path(bytes: Vec<u8>) -> PathBuf {
+    use std::{ffi::OsString, os::unix::ffi::OsStringExt};
+    PathBuf::from(OsString::from_vec(bytes))
+}
+
+// windows paths are expected as utf-8
+#[cfg(not(unix))]
+fn bytes_to_path(bytes: Vec<u8>) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// the path as it is, followed by NUL, e.g. for xargs -0
+#[cfg(unix)]
+fn write_path0<W: Write>(handle: &mut W, path: &Path) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    handle.write_all(path.as_os_str().as_bytes())?;
+    handle.write_all(b"\0")
+}
+
+#[cfg(not(unix))]
+fn write_path0<W: Write>(handle: &mut W, path: &Path) -> io::Result<()> {
+    write!(handle, "{}\0", path.to_string_lossy())
+}
+
+fn report_exec_status(status: ExecStatus) -> bool {
+    if status.failed > 0 {
+        error!(
+            "{} of {} executed commands failed",
+            status.failed, status.commands
+        );
+        return false;
+    }
+
+    true
+}
+
+fn report_error(err: &SearchError) {
+    let path = err.path().unwrap_or(Path::new("")).display();
+    if let Some(ancestor) = err.loop_ancestor() {
+        warn!(
+            "Symlink loop: \'{}\' points to its ancestor \'{}\'",
+            path,
+            ancestor.display()
+        );
+    } else if let Some(inner) = err.io_error() {
+        match inner.kind() {
+            io::ErrorKind::InvalidData => {
+                warn!("Entry \'{}\' contains invalid data: {}", path, inner)
+            }
+            io::ErrorKind::NotFound => {
+                warn!("Entry \'{}\' not found: {}", path, inner);
+            }
+            io::ErrorKind::PermissionDenied => {
+                warn!("Missing permission to read entry \'{}\': {}", path, inner)
+            }
+            _ => {
+                error!(
+                    "Failed to access entry: \'{}\'\nUnexpected error occurred: {}",
+                    path, inner
+                )
+            }
         }
     }
 }
 
 fn match_pattern_and_print<W: Write>(
     handle: &mut W,
-    name: String,
-    parent: String,
+    hit: &Hit,
+    searcher: &Searcher,
     config: &Config,
     pb: Option<ProgressBar>,
-    search_hits: &mut u64,
 ) {
-    // check for pattern match in filename via aho-corasick algorithm
-    if config.pattern_ac.is_match(&name) && !config.exclude_ac.is_match(&name) {
-        *search_hits += 1;
-
-        if !config.count_flag {
-            if config.performance_flag {
-                // don't use "file://" to make the path clickable in Windows Terminal -> otherwise output can't be piped easily to another program
-                writeln!(handle, "{}", format!("{}/{}", parent, name)).unwrap_or_else(|err| {
+    if !config.count_flag {
+        let parent = hit.parent();
+        let name = &hit.name;
+
+        if config.json_flag {
+            write_json_hit(handle, hit, &parent);
+        } else if config.print0_flag {
+            // only the path, content matches print the file once
+            write_path0(handle, &hit.path).unwrap_or_else(|err| {
+                error!("Error writing to stdout: {err}");
+            });
+        } else if config.performance_flag {
+            // don't use "file://" to make the path clickable in Windows Terminal -> otherwise output can't be piped easily to another program
+            if hit.lines.is_empty() {
+                writeln!(handle, "{}/{}", parent, name).unwrap_or_else(|err| {
                     error!("Error writing to stdout: {err}");
                 });
-            } else {
-                match pb.clone() {
-                    Some(pb) => {
-                        let name_with_hi_pattern = highlight_pattern_in_name(&name, &config);
-                        // use "file://" to make the path clickable in Windows Terminal"
-                        pb.println(format!(
-                            "file://{}/{}",
-                            parent,
-                            name_with_hi_pattern.truecolor(59, 179, 140)
-                        ))
-                    }
-                    None => {}
-                }
             }
+
+            // grep style "path:number:line" for content matches
+            for line in &hit.lines {
+                writeln!(handle, "{}/{}:{}:{}", parent, name, line.number, line.line)
+                    .unwrap_or_else(|err| {
+                        error!("Error writing to stdout: {err}");
+                    });
+            }
+        } else if let Some(pb) = pb.clone() {
+            let spans = searcher.match_spans(name);
+            let name_with_hi_pattern = highlight_pattern_in_name(name, &spans);
+            // use "file://" to make the path clickable in Windows Terminal"
+            // mark hits reached by following a symlink
+            let link = if hit.via_link { " (via symlink)" } else { "" };
+            pb.println(format!(
+                "file://{}/{}{}",
+                parent,
+                name_with_hi_pattern.truecolor(59, 179, 140),
+                link.dimmed()
+            ));
+
+            for line in &hit.lines {
+                let spans = searcher.match_spans(&line.line);
+                pb.println(format!(
+                    "  {} {}",
+                    format!("{}:", line.number).dimmed(),
+                    highlight_pattern_in_name(&line.line, &spans)
+                ));
+            }
+        }
+    }
+}
+
+// a former match that was deleted or renamed while watching
+fn print_removed<W: Write>(handle: &mut W, path: &Path, config: &Config, pb: Option<&ProgressBar>) {
+    // only new matches, a removed path can't be told apart
+    if config.print0_flag {
+        return;
+    }
+
+    let parent = path
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .to_string_lossy()
+        .replace('\\', "/");
+    let name = path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy();
+
+    if config.json_flag {
+        let object = json!({
+            "path": path.to_string_lossy(),
+            "name": name,
+            "parent": parent,
+            "event": "removed",
+        });
+        writeln!(handle, "{}", object).unwrap_or_else(|err| {
+            error!("Error writing to stdout: {err}");
+        });
+    } else if config.performance_flag {
+        writeln!(handle, "{}/{} (removed)", parent, name).unwrap_or_else(|err| {
+            error!("Error writing to stdout: {err}");
+        });
+    } else if let Some(pb) = pb {
+        pb.println(format!(
+            "file://{}/{}{}",
+            parent,
+            name.dimmed().strikethrough(),
+            " (removed)".truecolor(250, 0, 104)
+        ));
+    }
+}
+
+// one object per line
+fn write_json_hit<W: Write>(handle: &mut W, hit: &Hit, parent: &str) {
+    let metadata = hit_metadata(hit);
+    let mtime = metadata
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+
+    let mut object = json!({
+        "path": hit.path.to_string_lossy(),
+        "name": hit.name,
+        "parent": parent,
+        "type": hit.file_kind.as_str(),
+        "size": metadata.as_ref().map(|m| m.len()),
+        "mtime": mtime,
+        "via_link": hit.via_link,
+    });
+
+    if let Some(score) = hit.score {
+        object["score"] = json!(score);
+    }
+
+    if !hit.lines.is_empty() {
+        object["lines"] = hit
+            .lines
+            .iter()
+            .map(|line| json!({ "number": line.number, "line": line.line }))
+            .collect();
+    }
+
+    writeln!(handle, "{}", object).unwrap_or_else(|err| {
+        error!("Error writing to stdout: {err}");
+    });
+}
+
+// same numbers as get_search_hits_long
+fn write_json_stats<W: Write>(handle: &mut W, stats: &Stats, report: &Report, start: Instant) {
+    let duration = start.elapsed();
+    let object = json!({
+        "stats": {
+            "entries": stats.entry_count,
+            "dirs": stats.dir_count,
+            "errors": stats.error_count,
+            "matches": stats.search_hits,
+            "matched_bytes": stats.matched_bytes,
+            "duration_ms": duration.as_millis() as u64,
+            "entries_per_sec": entries_per_sec(stats.entry_count, duration),
+            "types": report.kinds,
+            "extensions": report.extensions,
+            "largest": report.largest.as_ref().map(|(size, path)| json!({
+                "path": path.to_string_lossy(),
+                "size": size,
+            })),
+            "deepest": report.deepest.as_ref().map(|(depth, path)| json!({
+                "path": path.to_string_lossy(),
+                "depth": depth,
+            })),
+            "error_kinds": report.errors,
+        }
+    });
+
+    writeln!(handle, "{}", object).unwrap_or_else(|err| {
+        error!("Error writing to stdout: {err}");
+    });
+}
+
+// rounded, 0 if the search took no measurable time
+fn entries_per_sec(entry_count: u64, duration: Duration) -> u64 {
+    let secs = duration.as_secs_f64();
+    if secs > 0.0 {
+        (entry_count as f64 / secs).round() as u64
+    } else {
+        0
+    }
+}
+
+fn print_index_stats(index: &Index, stats: IndexStats, start: Instant) {
+    for root in index.roots() {
+        println!("{} {}", "Indexed:".italic().dimmed(), root.display());
+    }
+
+    println!(
+        "\n{} {}",
+        stats.entries.to_string().dimmed(),
+        "entries indexed".dimmed()
+    );
+    println!(
+        "{} {}",
+        stats.rescanned.to_string().dimmed(),
+        format!("of {} directories read", stats.dirs).dimmed()
+    );
+    if stats.errors > 0 {
+        println!(
+            "{} {}",
+            stats.errors.to_string().truecolor(250, 0, 104),
+            "directories not readable".dimmed()
+        );
+    }
+
+    println!(
+        "{}",
+        HumanDuration(start.elapsed())
+            .to_string()
+            .truecolor(112, 110, 255)
+    );
+}
+
+// duplicate sets with the most wasted bytes first, followed by a summary
+fn print_dupes<W: Write>(handle: &mut W, dupes: &Dupes, file_count: usize, start: Instant) {
+    for set in &dupes.sets {
+        writeln!(
+            handle,
+            "{} {}",
+            format!("{} x {}", set.paths.len(), HumanBytes(set.size)).bold(),
+            format!("({} wasted)", HumanBytes(set.wasted())).truecolor(250, 0, 104)
+        )
+        .unwrap_or_else(|err| error!("Error writing to stdout: {err}"));
+
+        for path in &set.paths {
+            let parent = path
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .to_string_lossy()
+                .replace('\\', "/");
+            let name = path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy();
+            writeln!(
+                handle,
+                "  {}/{}",
+                parent.dimmed(),
+                name.truecolor(59, 179, 140)
+            )
+            .unwrap_or_else(|err| error!("Error writing to stdout: {err}"));
         }
+        writeln!(handle).unwrap_or_else(|err| error!("Error writing to stdout: {err}"));
+    }
+
+    let duplicates = dupes
+        .sets
+        .iter()
+        .map(|set| set.paths.len() - 1)
+        .sum::<usize>();
+    let wasted = dupes.sets.iter().map(DupeSet::wasted).sum::<u64>();
+    writeln!(
+        handle,
+        "{} {}\n{} {}\n{} {}",
+        file_count.to_string().dimmed(),
+        format!("files compared, {} of them read", dupes.hashed).dimmed(),
+        duplicates.to_string().dimmed(),
+        format!("duplicates in {} sets", dupes.sets.len()).dimmed(),
+        HumanBytes(wasted).to_string().truecolor(250, 0, 104),
+        "wasted".dimmed()
+    )
+    .unwrap_or_else(|err| error!("Error writing to stdout: {err}"));
+    if dupes.errors > 0 {
+        writeln!(
+            handle,
+            "{} {}",
+            dupes.errors.to_string().truecolor(250, 0, 104),
+            "files not readable".dimmed()
+        )
+        .unwrap_or_else(|err| error!("Error writing to stdout: {err}"));
     }
+    writeln!(
+        handle,
+        "{}",
+        HumanDuration(start.elapsed())
+            .to_string()
+            .truecolor(112, 110, 255)
+    )
+    .unwrap_or_else(|err| error!("Error writing to stdout: {err}"));
 }
 
-fn get_search_hits_short(search_hits: u64, entry_count: u64, error_count: u64, start: Instant) {
+fn get_search_hits_short(stats: &Stats, start: Instant) {
     println!(
-        "[{}   {} {} {}]",
+        "[{}   {} {} {} {}]",
         HumanDuration(start.elapsed())
             .to_string()
             .truecolor(112, 110, 255),
-        entry_count.to_string().dimmed(),
-        error_count.to_string().truecolor(250, 0, 104),
-        search_hits.to_string().truecolor(59, 179, 140).bold(),
+        stats.entry_count.to_string().dimmed(),
+        stats.error_count.to_string().truecolor(250, 0, 104),
+        stats.search_hits.to_string().truecolor(59, 179, 140).bold(),
+        HumanBytes(stats.matched_bytes).to_string().dimmed(),
     );
 }
 
-fn get_search_hits_long(search_hits: u64, entry_count: u64, error_count: u64, start: Instant) {
+fn get_search_hits_long(stats: &Stats, report: &Report, start: Instant) {
+    let duration = start.elapsed();
+    let (search_hits, error_count) = (stats.search_hits, stats.error_count);
+
     println!(
         "\n{} {}",
-        entry_count.to_string().dimmed(),
+        stats.entry_count.to_string().dimmed(),
         "entries searched".dimmed()
     );
+    println!(
+        "{} {}",
+        stats.dir_count.to_string().dimmed(),
+        "directories visited".dimmed()
+    );
 
     if error_count == 1 {
         println!(
@@ -680,6 +2024,13 @@ fn get_search_hits_long(search_hits: u64, entry_count: u64, error_count: u64, st
             "errors occured".dimmed()
         );
     }
+    for (kind, count) in &report.errors {
+        println!(
+            "  {} {}",
+            count.to_string().truecolor(250, 0, 104),
+            kind.dimmed()
+        );
+    }
 
     if search_hits == 0 {
         println!(
@@ -698,55 +2049,86 @@ fn get_search_hits_long(search_hits: u64, entry_count: u64, error_count: u64, st
         );
     }
 
+    if search_hits > 0 {
+        println!(
+            "{} {}",
+            HumanBytes(stats.matched_bytes).to_string().dimmed(),
+            "in matched files".dimmed()
+        );
+
+        let kinds = report
+            .kinds
+            .iter()
+            .map(|(kind, count)| format!("{count} {kind}"))
+            .collect::<Vec<_>>();
+        println!("  {} {}", "types:".dimmed(), kinds.join(", "));
+
+        // the rest only as a number, there may be hundreds of extensions
+        let extensions = report.top_extensions();
+        if !extensions.is_empty() {
+            let mut shown = extensions
+                .iter()
+                .take(TOP_EXTENSIONS)
+                .map(|(extension, count)| match *extension {
+                    "" => format!("{count} without"),
+                    extension => format!("{count} {extension}"),
+                })
+                .collect::<Vec<_>>();
+            if extensions.len() > TOP_EXTENSIONS {
+                shown.push(format!("{} more", extensions.len() - TOP_EXTENSIONS));
+            }
+            println!("  {} {}", "extensions:".dimmed(), shown.join(", "));
+        }
+
+        if let Some((size, path)) = &report.largest {
+            println!(
+                "  {} {} {}",
+                "largest:".dimmed(),
+                HumanBytes(*size),
+                path.display()
+            );
+        }
+        if let Some((depth, path)) = &report.deepest {
+            println!(
+                "  {} depth {} {}",
+                "deepest:".dimmed(),
+                depth,
+                path.display()
+            );
+        }
+    }
+
     println!(
-        "{}",
-        HumanDuration(start.elapsed())
+        "{} {}",
+        entries_per_sec(stats.entry_count, duration)
             .to_string()
-            .truecolor(112, 110, 255)
+            .dimmed(),
+        "entries per second".dimmed()
+    );
+    println!(
+        "{}",
+        HumanDuration(duration).to_string().truecolor(112, 110, 255)
     );
 }
 
-fn highlight_pattern_in_name(name: &str, config: &Config) -> String {
-    // find first byte of pattern in filename
-    let pat_in_name = name.find(&config.pattern).unwrap_or_else(|| 9999999999);
-
-    if pat_in_name == 9999999999 {
+// colourize the matched byte ranges in the filename
+fn highlight_pattern_in_name(name: &str, spans: &[Range<usize>]) -> String {
+    if spans.is_empty() {
         // if no pattern found return just the filename
         return name.to_string();
-    } else {
-        let first_from_name = &name[..pat_in_name];
-        let last_from_name = &name[(pat_in_name + config.pattern.len())..];
-        // colourize the pattern in the filename
-        let highlighted_pattern = config.pattern.truecolor(112, 110, 255).to_string();
-
-        let mut result = String::from(first_from_name);
-        result.push_str(&highlighted_pattern);
-        result.push_str(last_from_name);
-
-        result.to_string()
     }
-}
 
-// check entries if hidden and compare to hidden flag
-fn file_check(entry: &DirEntry, config: &Config) -> bool {
-    // TODO bottleneck
-    if config.no_hidden_flag && is_hidden(&entry.path().to_path_buf()).unwrap_or(false) {
-        return false;
+    let mut result = String::new();
+    let mut last = 0;
+    for span in spans {
+        result.push_str(&name[last..span.start]);
+        let matched = &name[span.clone()];
+        result.push_str(&matched.truecolor(112, 110, 255).to_string());
+        last = span.end;
     }
+    result.push_str(&name[last..]);
 
-    return true;
-}
-
-// TODO bottleneck
-fn is_hidden(file_path: &PathBuf) -> std::io::Result<bool> {
-    let metadata = fs::metadata(file_path)?;
-    let attributes = metadata.file_attributes();
-
-    if (attributes & 0x2) > 0 {
-        Ok(true)
-    } else {
-        Ok(false)
-    }
+    result
 }
 
 fn check_create_config_dir() -> io::Result<PathBuf> {
@@ -767,26 +2149,60 @@ fn check_create_config_dir() -> io::Result<PathBuf> {
     Ok(new_dir)
 }
 
+// a missing index is reported with a hint how to build one
+fn open_index(config_dir: &Path) -> Result<Index, IndexError> {
+    let path = config_dir.join(INDEX_FILE);
+    Index::open(&path).map_err(|err| match err {
+        IndexError::Io(err) if err.kind() == io::ErrorKind::NotFound => {
+            IndexError::Io(io::Error::new(
+                err.kind(),
+                "No index found, build one via sf index build <PATH>",
+            ))
+        }
+        err => err,
+    })
+}
+
+// search indicating spinner
+fn spinner(message: &str) -> ProgressBar {
+    let spinner_style = ProgressStyle::with_template("{spinner:.red} {msg}").unwrap();
+    let pb = ProgressBar::new_spinner();
+    pb.enable_steady_tick(Duration::from_millis(120));
+    pb.set_style(spinner_style);
+    pb.set_message(format!("{}", message.truecolor(250, 0, 104)));
+
+    pb
+}
+
 fn show_log_file(config_dir: &PathBuf) -> io::Result<String> {
     let log_path = Path::new(&config_dir).join("sf.log");
     match log_path.try_exists()? {
-        true => {
-            return Ok(format!(
-                "{} {}\n{}",
-                "Log location:".italic().dimmed(),
-                &log_path.display(),
-                fs::read_to_string(&log_path)?
-            ));
-        }
-        false => {
-            return Ok(format!(
-                "{} {}",
-                "No log file found:"
-                    .truecolor(250, 0, 104)
-                    .bold()
-                    .to_string(),
-                log_path.display()
-            ))
-        }
+        true => Ok(format!(
+            "{} {}\n{}",
+            "Log location:".italic().dimmed(),
+            &log_path.display(),
+            fs::read_to_string(&log_path)?
+        )),
+        false => Ok(format!(
+            "{} {}",
+            "No log file found:".truecolor(250, 0, 104).bold(),
+            log_path.display()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn print0_keeps_the_path_bytes() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = Path::new("back\\slash").join(OsStr::from_bytes(b"bad\xffname"));
+        let mut output = Vec::new();
+        write_path0(&mut output, &path).unwrap();
+        assert_eq!(output, b"back\\slash/bad\xffname\0");
     }
 }
@@ -0,0 +1,243 @@
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+
+use std::ops::Range;
+
+use crate::{fold, fuzzy};
+
+/// How the search pattern is matched against entry names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchMode {
+    /// The name contains the pattern
+    #[default]
+    Literal,
+    /// The name matches the pattern as regular expression
+    Regex,
+    /// The name matches the pattern as glob
+    ///
+    /// Patterns containing a `/` are matched against the path relative to the search path,
+    /// `**` matches any number of directories then.
+    Glob,
+    /// The characters of the pattern appear in the name in the same order
+    ///
+    /// Every hit gets a score, favoring matches at word boundaries, consecutive characters and shorter names.
+    Fuzzy,
+}
+
+// compiled form of one or more patterns
+// the aho-corasick automaton is boxed, it is an order of magnitude larger than a regex handle
+#[derive(Debug)]
+pub(crate) enum Matcher {
+    Literal(Box<AhoCorasick>),
+    // case insensitive literal, built from the case folded patterns
+    // non-ascii haystacks are case folded before matching, ascii ones only ignore the case of ascii letters
+    Folded(Box<AhoCorasick>),
+    Regex(Regex),
+    Glob {
+        glob: GlobMatcher,
+        // match against the relative path instead of the name
+        on_path: bool,
+    },
+    Fuzzy {
+        pattern: Vec<char>,
+        case_insensitive: bool,
+    },
+}
+
+impl Matcher {
+    // leftmost-longest, so overlapping patterns highlight the longer one
+    // even ascii patterns need the folded haystack, e.g. "ss" matches "Straße"
+    pub(crate) fn literal(patterns: &[String], case_insensitive: bool) -> Self {
+        if case_insensitive {
+            let folded = patterns.iter().map(|p| fold::fold(p)).collect::<Vec<_>>();
+            let ac = AhoCorasickBuilder::new()
+                .ascii_case_insensitive(true)
+                .match_kind(MatchKind::LeftmostLongest)
+                .build(folded);
+            return Matcher::Folded(Box::new(ac));
+        }
+
+        let ac = AhoCorasickBuilder::new()
+            .match_kind(MatchKind::LeftmostLongest)
+            .build(patterns);
+
+        Matcher::Literal(Box::new(ac))
+    }
+
+    // multiple patterns are joined into a single alternation
+    // no patterns never match, like an empty aho-corasick automaton
+    pub(crate) fn regex(patterns: &[String], case_insensitive: bool) -> Result<Self, regex::Error> {
+        if patterns.is_empty() {
+            return Ok(Self::literal(patterns, case_insensitive));
+        }
+
+        // keep a single pattern as is, so parse errors point into the pattern the user wrote
+        let joined = match patterns {
+            [pattern] => pattern.to_owned(),
+            _ => patterns
+                .iter()
+                .map(|p| format!("(?:{p})"))
+                .collect::<Vec<_>>()
+                .join("|"),
+        };
+
+        let re = RegexBuilder::new(&joined)
+            .case_insensitive(case_insensitive)
+            .build()?;
+
+        Ok(Matcher::Regex(re))
+    }
+
+    // a glob has to match the whole name, so `*` and `?` never cross a path separator
+    pub(crate) fn glob(pattern: &str, case_insensitive: bool) -> Result<Self, globset::Error> {
+        let glob = GlobBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .literal_separator(true)
+            .build()?
+            .compile_matcher();
+
+        Ok(Matcher::Glob {
+            glob,
+            on_path: pattern.contains('/'),
+        })
+    }
+
+    pub(crate) fn fuzzy(pattern: &str, case_insensitive: bool) -> Self {
+        Matcher::Fuzzy {
+            pattern: pattern.chars().collect(),
+            case_insensitive,
+        }
+    }
+
+    // the haystack is the relative path for globs with a `/`, else the name
+    pub(crate) fn on_path(&self) -> bool {
+        matches!(self, Matcher::Glob { on_path: true, .. })
+    }
+
+    pub(crate) fn is_match(&self, haystack: &str) -> bool {
+        match self {
+            Matcher::Literal(ac) => ac.is_match(haystack),
+            Matcher::Folded(ac) if haystack.is_ascii() => ac.is_match(haystack),
+            Matcher::Folded(ac) => ac.is_match(fold::fold(haystack)),
+            Matcher::Regex(re) => re.is_match(haystack),
+            Matcher::Glob { glob, .. } => glob.is_match(haystack),
+            Matcher::Fuzzy {
+                pattern,
+                case_insensitive,
+            } => fuzzy::is_subsequence(pattern, haystack, *case_insensitive),
+        }
+    }
+
+    // best fuzzy match, only for the fuzzy matcher
+    pub(crate) fn fuzzy_match(&self, haystack: &str) -> Option<fuzzy::FuzzyMatch> {
+        match self {
+            Matcher::Fuzzy {
+                pattern,
+                case_insensitive,
+            } => fuzzy::fuzzy_match(pattern, haystack, *case_insensitive),
+            _ => None,
+        }
+    }
+}
+
+// whether smart case has to match case sensitively
+// the character after a backslash of a regex is an escape, e.g. \D or \W
+pub(crate) fn has_uppercase(pattern: &str, regex: bool) -> bool {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if regex && c == '\\' {
+            chars.next();
+        } else if c.is_uppercase() {
+            return true;
+        }
+    }
+
+    false
+}
+
+// byte ranges of every non-overlapping, non-empty literal match
+pub(crate) fn literal_spans(ac: &AhoCorasick, haystack: &str) -> Vec<Range<usize>> {
+    ac.find_iter(haystack)
+        .filter(|m| m.start() < m.end())
+        .map(|m| m.start()..m.end())
+        .collect()
+}
+
+// like literal_spans, mapped back from the case folded haystack
+pub(crate) fn folded_spans(ac: &AhoCorasick, haystack: &str) -> Vec<Range<usize>> {
+    if haystack.is_ascii() {
+        return literal_spans(ac, haystack);
+    }
+
+    let (folded, origins) = fold::fold_with_offsets(haystack);
+    ac.find_iter(&folded)
+        .filter(|m| m.start() < m.end())
+        .map(|m| origins[m.start()].start..origins[m.end() - 1].end)
+        .collect()
+}
+
+// byte ranges of every non-empty regex match
+pub(crate) fn regex_spans(re: &Regex, haystack: &str) -> Vec<Range<usize>> {
+    re.find_iter(haystack)
+        .filter(|m| !m.range().is_empty())
+        .map(|m| m.range())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn literal(pattern: &str) -> Matcher {
+        Matcher::literal(&[pattern.to_string()], true)
+    }
+
+    fn spans(matcher: &Matcher, haystack: &str) -> Vec<Range<usize>> {
+        match matcher {
+            Matcher::Literal(ac) => literal_spans(ac, haystack),
+            Matcher::Folded(ac) => folded_spans(ac, haystack),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn case_insensitive_literal() {
+        assert!(literal("straße").is_match("STRASSE"));
+        assert!(literal("STRASSE").is_match("straße"));
+        assert!(literal("ss").is_match("Straße"));
+        assert!(literal("SS").is_match("STRAẞE"));
+        assert!(literal("file").is_match("ﬁle"));
+        assert!(literal("привет").is_match("ПРИВЕТ"));
+        assert!(literal("abc").is_match("xABCx"));
+        assert!(!literal("abc").is_match("xABx"));
+        assert!(!literal("ss").is_match("Strase"));
+    }
+
+    #[test]
+    fn case_sensitive_literal() {
+        let matcher = Matcher::literal(&["ss".to_string()], false);
+        assert!(matcher.is_match("ss"));
+        assert!(!matcher.is_match("SS"));
+        assert!(!matcher.is_match("Straße"));
+    }
+
+    #[test]
+    fn folded_spans_point_into_the_name() {
+        assert_eq!(spans(&literal("ss"), "Straße"), vec![4..6]);
+        assert_eq!(spans(&literal("straße"), "x STRASSE"), vec![2..9]);
+        assert_eq!(spans(&literal("strasse"), "Straße.txt"), vec![0..7]);
+        assert_eq!(spans(&literal("ä"), "ÄaÄ"), [0..2, 3..5]);
+        assert_eq!(spans(&literal("abc"), "xABCx"), vec![1..4]);
+    }
+
+    #[test]
+    fn smart_case() {
+        assert!(!has_uppercase("abc", false));
+        assert!(has_uppercase("aBc", false));
+        assert!(has_uppercase("straẞe", false));
+        assert!(has_uppercase("\\D", false));
+        assert!(!has_uppercase("\\D\\W", true));
+        assert!(has_uppercase("\\dA", true));
+    }
+}
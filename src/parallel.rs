@@ -0,0 +1,411 @@
+use std::{
+    fs::{self, FileType},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use crate::entry::Entry;
+
+// an entry found by the parallel walker
+#[derive(Debug)]
+pub(crate) struct ParEntry {
+    path: PathBuf,
+    file_type: FileType,
+    depth: usize,
+    // a followed symlink, its metadata is the one of the target
+    followed: bool,
+    // a followed symlink or below one
+    via_link: bool,
+}
+
+impl ParEntry {
+    pub(crate) fn via_link(&self) -> bool {
+        self.via_link
+    }
+}
+
+impl Entry for ParEntry {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    fn depth(&self) -> usize {
+        self.depth
+    }
+
+    fn metadata(&self) -> io::Result<fs::Metadata> {
+        if self.followed {
+            fs::metadata(&self.path)
+        } else {
+            fs::symlink_metadata(&self.path)
+        }
+    }
+}
+
+// errors of the parallel walker, with the path that failed
+#[derive(Debug)]
+pub(crate) enum WalkError {
+    Io { path: PathBuf, err: io::Error },
+    // a followed symlink points to one of its ancestors
+    Loop { path: PathBuf, ancestor: PathBuf },
+}
+
+pub(crate) type WalkResult = Result<ParEntry, WalkError>;
+
+// the directories above a job, only tracked when following symlinks
+struct Ancestor {
+    // compared to detect loops
+    canonical: PathBuf,
+    // reported in loop errors, like walkdir does
+    path: PathBuf,
+    parent: Option<Arc<Ancestor>>,
+}
+
+impl Ancestor {
+    fn find(self: &Arc<Self>, canonical: &Path) -> Option<&Path> {
+        let mut current = Some(self);
+        while let Some(ancestor) = current {
+            if ancestor.canonical == canonical {
+                return Some(&ancestor.path);
+            }
+            current = ancestor.parent.as_ref();
+        }
+
+        None
+    }
+}
+
+// a directory to read
+struct Dir {
+    path: PathBuf,
+    depth: usize,
+    via_link: bool,
+    ancestors: Option<Arc<Ancestor>>,
+}
+
+struct Walker<F, V> {
+    max_depth: usize,
+    // like walkdir's filter_entry: rejected directories are not entered
+    filter: F,
+    // gets every entry and error, returns false to stop the walk
+    visit: V,
+    quit: AtomicBool,
+}
+
+// walk the directory tree below root on a pool of `threads` worker threads
+// every directory is read as its own job, idle workers steal them from busy ones
+// returns immediately, the walk runs in the background until every entry is visited
+pub(crate) fn spawn<F, V>(
+    root: PathBuf,
+    max_depth: usize,
+    threads: usize,
+    follow: bool,
+    filter: F,
+    visit: V,
+) where
+    F: Fn(&ParEntry) -> bool + Send + Sync + 'static,
+    V: Fn(WalkResult) -> bool + Send + Sync + 'static,
+{
+    thread::spawn(move || {
+        let walker = Walker {
+            max_depth,
+            filter,
+            visit,
+            quit: AtomicBool::new(false),
+        };
+
+        let pool = match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => pool,
+            Err(err) => {
+                let err = io::Error::other(err);
+                (walker.visit)(Err(WalkError::Io { path: root, err }));
+                return;
+            }
+        };
+
+        pool.scope(|scope| {
+            // like walkdir the root is always followed if it is a symlink
+            match fs::metadata(&root) {
+                Ok(metadata) => {
+                    let ancestors = follow.then(|| {
+                        Arc::new(Ancestor {
+                            canonical: fs::canonicalize(&root).unwrap_or_else(|_| root.clone()),
+                            path: root.clone(),
+                            parent: None,
+                        })
+                    });
+                    // the metadata of a symlinked root is the one of its target
+                    let followed = fs::symlink_metadata(&root)
+                        .is_ok_and(|metadata| metadata.file_type().is_symlink());
+                    let entry = ParEntry {
+                        path: root,
+                        file_type: metadata.file_type(),
+                        depth: 0,
+                        followed,
+                        via_link: false,
+                    };
+                    walker.handle(scope, entry, ancestors);
+                }
+                Err(err) => {
+                    (walker.visit)(Err(WalkError::Io { path: root, err }));
+                }
+            }
+        });
+    });
+}
+
+impl<F, V> Walker<F, V>
+where
+    F: Fn(&ParEntry) -> bool + Send + Sync,
+    V: Fn(WalkResult) -> bool + Send + Sync,
+{
+    // the ancestors of a directory entry include the directory itself
+    fn handle<'s>(
+        &'s self,
+        scope: &rayon::Scope<'s>,
+        entry: ParEntry,
+        ancestors: Option<Arc<Ancestor>>,
+    ) {
+        if self.quit.load(Ordering::Relaxed) || !(self.filter)(&entry) {
+            return;
+        }
+
+        let subdir = (entry.file_type.is_dir() && entry.depth < self.max_depth).then(|| Dir {
+            path: entry.path.clone(),
+            depth: entry.depth + 1,
+            via_link: entry.via_link,
+            ancestors,
+        });
+
+        self.emit(Ok(entry));
+
+        if let Some(dir) = subdir {
+            scope.spawn(move |scope| self.read_dir(scope, dir));
+        }
+    }
+
+    fn read_dir<'s>(&'s self, scope: &rayon::Scope<'s>, dir: Dir) {
+        let read_dir = match fs::read_dir(&dir.path) {
+            Ok(read_dir) => read_dir,
+            Err(err) => {
+                self.emit(Err(WalkError::Io {
+                    path: dir.path,
+                    err,
+                }));
+                return;
+            }
+        };
+
+        for child in read_dir {
+            if self.quit.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let (path, file_type) =
+                match child.and_then(|c| c.file_type().map(|file_type| (c.path(), file_type))) {
+                    Ok(child) => child,
+                    Err(err) => {
+                        self.emit(Err(WalkError::Io {
+                            path: dir.path.clone(),
+                            err,
+                        }));
+                        continue;
+                    }
+                };
+
+            let entry = ParEntry {
+                path,
+                file_type,
+                depth: dir.depth,
+                followed: false,
+                via_link: dir.via_link,
+            };
+            match self.follow(entry, &dir) {
+                Ok((entry, ancestors)) => self.handle(scope, entry, ancestors),
+                Err(err) => self.emit(Err(err)),
+            }
+        }
+    }
+
+    // replace the type of a symlink by the type of its target
+    // and keep track of the canonical paths of directories to detect loops
+    // only when following symlinks, that is if the directory has ancestors
+    fn follow(
+        &self,
+        mut entry: ParEntry,
+        dir: &Dir,
+    ) -> Result<(ParEntry, Option<Arc<Ancestor>>), WalkError> {
+        let Some(parent) = &dir.ancestors else {
+            return Ok((entry, None));
+        };
+
+        let canonical = if entry.file_type.is_symlink() {
+            let metadata = fs::metadata(&entry.path).map_err(|err| WalkError::Io {
+                path: entry.path.clone(),
+                err,
+            })?;
+            entry.file_type = metadata.file_type();
+            entry.followed = true;
+            entry.via_link = true;
+
+            if !entry.file_type.is_dir() {
+                return Ok((entry, None));
+            }
+
+            let canonical = fs::canonicalize(&entry.path).map_err(|err| WalkError::Io {
+                path: entry.path.clone(),
+                err,
+            })?;
+            if let Some(ancestor) = parent.find(&canonical) {
+                return Err(WalkError::Loop {
+                    path: entry.path,
+                    ancestor: ancestor.to_path_buf(),
+                });
+            }
+            canonical
+        } else if entry.file_type.is_dir() {
+            parent.canonical.join(entry.file_name())
+        } else {
+            return Ok((entry, None));
+        };
+
+        let ancestors = Arc::new(Ancestor {
+            canonical,
+            path: entry.path.clone(),
+            parent: Some(Arc::clone(parent)),
+        });
+        Ok((entry, Some(ancestors)))
+    }
+
+    fn emit(&self, result: WalkResult) {
+        if !(self.visit)(result) {
+            self.quit.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{temp_dir::TempDir, FileKind, SearchBuilder, Stats};
+
+    use std::path::{Path, PathBuf};
+
+    type Found = (PathBuf, usize, FileKind, bool, Option<u64>);
+
+    fn tree(name: &str) -> TempDir {
+        let dir = TempDir::new(name);
+        dir.create(&[
+            "a.txt",
+            "b/",
+            "b/ab.rs",
+            "b/c/",
+            "b/c/abc.md",
+            "b/c/d/abcd",
+            "e/",
+        ]);
+        dir.write("b/size", "12345");
+        dir.write(".hidden/a.txt", "1");
+        dir.write(".hidden/deeper/a", "12");
+        dir
+    }
+
+    // hits and the paths of the errors in path order, and the stats once the search is finished
+    fn search(builder: SearchBuilder) -> (Vec<Found>, Vec<Option<PathBuf>>, Stats) {
+        let mut searcher = builder.build().unwrap();
+        let mut found = Vec::new();
+        let mut errors = Vec::new();
+        for hit in searcher.by_ref() {
+            match hit {
+                Ok(hit) => found.push((hit.path, hit.depth, hit.file_kind, hit.via_link, hit.size)),
+                Err(err) => errors.push(err.path().map(Path::to_path_buf)),
+            }
+        }
+        found.sort_by(|a, b| a.0.cmp(&b.0));
+        errors.sort();
+
+        (found, errors, searcher.stats())
+    }
+
+    // the same hits and stats with the sequential and the parallel walker
+    fn assert_like_sequential(build: impl Fn() -> SearchBuilder) -> Vec<Found> {
+        let (sequential, errors, stats) = search(build().threads(1));
+        assert_eq!(stats.error_count, errors.len() as u64);
+        for threads in [2, 4] {
+            let (parallel, parallel_errors, parallel_stats) = search(build().threads(threads));
+            assert_eq!(parallel, sequential, "{} threads", threads);
+            assert_eq!(parallel_errors, errors, "{} threads", threads);
+            assert_eq!(parallel_stats, stats, "{} threads", threads);
+        }
+
+        sequential
+    }
+
+    #[test]
+    fn walk_like_the_sequential_walk() {
+        let dir = tree("parallel-walk");
+        let found = assert_like_sequential(|| SearchBuilder::new("", dir.path()));
+        assert_eq!(found.len(), 14);
+
+        let found =
+            assert_like_sequential(|| SearchBuilder::new("a", dir.path()).count_bytes(true));
+        assert_eq!(found.len(), 7);
+    }
+
+    #[test]
+    fn max_depth() {
+        let dir = tree("parallel-depth");
+        for depth in [0, 1, 2] {
+            let found = assert_like_sequential(|| SearchBuilder::new("", dir.path()).depth(depth));
+            assert!(found
+                .iter()
+                .all(|(_, found_depth, ..)| *found_depth <= depth as usize));
+        }
+    }
+
+    #[test]
+    fn excluded_hidden_dir() {
+        let dir = tree("parallel-hidden");
+        let found = assert_like_sequential(|| {
+            SearchBuilder::new("a", dir.path())
+                .no_hidden(true)
+                .depth(2)
+                .file_only(true)
+        });
+        let found = found
+            .into_iter()
+            .map(|(path, ..)| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect::<Vec<_>>();
+        assert_eq!(found, [PathBuf::from("a.txt"), PathBuf::from("b/ab.rs")]);
+
+        assert_like_sequential(|| SearchBuilder::new("", dir.path()).exclude(["c"]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn followed_links() {
+        let dir = tree("parallel-links");
+        std::os::unix::fs::symlink(dir.join("b/c"), dir.join("e/link")).unwrap();
+        // a loop, reported as error
+        std::os::unix::fs::symlink(dir.join("b"), dir.join("b/c/up")).unwrap();
+
+        assert_like_sequential(|| SearchBuilder::new("", dir.path()));
+        let found = assert_like_sequential(|| SearchBuilder::new("", dir.path()).follow(true));
+        assert!(found.iter().any(|(.., via_link, _)| *via_link));
+
+        let (_, errors, _) = search(SearchBuilder::new("", dir.path()).follow(true));
+        assert!(!errors.is_empty());
+    }
+}
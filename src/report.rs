@@ -0,0 +1,71 @@
+use sf::{FileKind, Hit, SearchError};
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+// details of the search results for --stats-long and the JSON stats
+#[derive(Debug, Default)]
+pub struct Report {
+    // matched files per extension, "" for files without one
+    pub extensions: BTreeMap<String, u64>,
+    // matches per file type
+    pub kinds: BTreeMap<&'static str, u64>,
+    // only known if the size of the matches is counted
+    pub largest: Option<(u64, PathBuf)>,
+    // depth below the search path
+    pub deepest: Option<(usize, PathBuf)>,
+    // errors per io::ErrorKind, e.g. PermissionDenied
+    pub errors: BTreeMap<String, u64>,
+}
+
+impl Report {
+    pub fn add_hit(&mut self, hit: &Hit) {
+        *self.kinds.entry(hit.file_kind.as_str()).or_default() += 1;
+
+        if hit.file_kind == FileKind::File {
+            let extension = hit
+                .path
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_string())
+                .unwrap_or_default();
+            *self.extensions.entry(extension).or_default() += 1;
+        }
+
+        // the first one wins a tie
+        if let Some(size) = hit.size {
+            let larger = match &self.largest {
+                Some((max, _)) => size > *max,
+                None => true,
+            };
+            if larger {
+                self.largest = Some((size, hit.path.clone()));
+            }
+        }
+        let deeper = match &self.deepest {
+            Some((max, _)) => hit.depth > *max,
+            None => true,
+        };
+        if deeper {
+            self.deepest = Some((hit.depth, hit.path.clone()));
+        }
+    }
+
+    pub fn add_error(&mut self, err: &SearchError) {
+        let kind = match err.io_error() {
+            Some(err) => format!("{:?}", err.kind()),
+            None if err.loop_ancestor().is_some() => "FilesystemLoop".to_string(),
+            None => "Other".to_string(),
+        };
+        *self.errors.entry(kind).or_default() += 1;
+    }
+
+    // most frequent first, ties by name
+    pub fn top_extensions(&self) -> Vec<(&str, u64)> {
+        let mut extensions = self
+            .extensions
+            .iter()
+            .map(|(extension, count)| (extension.as_str(), *count))
+            .collect::<Vec<_>>();
+        extensions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        extensions
+    }
+}
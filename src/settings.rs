@@ -0,0 +1,365 @@
+use clap::{parser::ValueSource, Arg, ArgAction, ArgMatches, Command};
+use toml::{Table, Value};
+
+use std::{fs, io, path::Path};
+
+pub const CONFIG_FILE: &str = "config.toml";
+
+// arguments that make no sense as a default
+const NOT_CONFIGURABLE: [&str; 9] = [
+    "args",
+    "override",
+    "profile",
+    "exec",
+    "exec-batch",
+    "watch",
+    "interactive",
+    "help",
+    "version",
+];
+
+// options from the config file merged beneath the command line arguments
+// keys are the long names of the arguments, e.g.
+//
+// [default]
+// no-hidden = true
+// exclude = ["target"]
+//
+// [profiles.code]
+// file = true
+// extension = ["rs", "py"]
+pub struct Settings<'a> {
+    matches: &'a ArgMatches,
+    options: Table,
+}
+
+impl<'a> Settings<'a> {
+    // only the command line arguments
+    pub fn cli_only(matches: &'a ArgMatches) -> Self {
+        Self {
+            matches,
+            options: Table::new(),
+        }
+    }
+
+    // the [default] table, overwritten by the given profile, overwritten by the command line arguments
+    // a missing config file is fine as long as no profile is requested
+    pub fn load(
+        matches: &'a ArgMatches,
+        cmd: &Command,
+        config_dir: &Path,
+        profile: Option<&str>,
+    ) -> Result<Self, String> {
+        let path = config_dir.join(CONFIG_FILE);
+        let mut file = match fs::read_to_string(&path) {
+            Ok(content) => content
+                .parse::<Table>()
+                .map_err(|err| format!("Unable to parse {}: {err}", path.display()))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Table::new(),
+            Err(err) => return Err(format!("Unable to read {}: {err}", path.display())),
+        };
+
+        let mut options = match file.remove("default") {
+            Some(Value::Table(default)) => validate(cmd, default, "[default]")?,
+            Some(_) => return Err("Expected a table for [default]".to_string()),
+            None => Table::new(),
+        };
+
+        let mut profiles = match file.remove("profiles") {
+            Some(Value::Table(profiles)) => profiles,
+            Some(_) => return Err("Expected a table for [profiles]".to_string()),
+            None => Table::new(),
+        };
+
+        if let Some(key) = file.keys().next() {
+            return Err(format!(
+                "Unknown key \'{key}\' in {}, expected [default] or [profiles.<NAME>]",
+                path.display()
+            ));
+        }
+
+        if let Some(name) = profile {
+            let section = format!("[profiles.{name}]");
+            match profiles.remove(name) {
+                Some(Value::Table(profile)) => {
+                    let profile = validate(cmd, profile, &section)?;
+                    overwrite(cmd, &mut options, profile.keys().map(String::as_str));
+                    options.extend(profile);
+                }
+                Some(_) => return Err(format!("Expected a table for {section}")),
+                None => {
+                    return Err(format!(
+                        "Profile \'{name}\' not found in {}",
+                        path.display()
+                    ))
+                }
+            }
+        }
+
+        // everything set on the command line wins
+        let cli_ids = cmd
+            .get_arguments()
+            .map(|arg| arg.get_id().as_str())
+            .filter(|id| matches.value_source(id) == Some(ValueSource::CommandLine));
+        overwrite(cmd, &mut options, cli_ids);
+
+        Ok(Self { matches, options })
+    }
+
+    pub fn flag(&self, id: &str) -> bool {
+        self.matches.get_flag(id) || self.options.get(id).and_then(Value::as_bool) == Some(true)
+    }
+
+    // single value, falls back to the default value of the argument
+    pub fn value(&self, id: &str) -> Option<String> {
+        match self.options.get(id) {
+            Some(value) => Some(value_to_string(value)),
+            None => self.matches.get_one::<String>(id).cloned(),
+        }
+    }
+
+    pub fn values(&self, id: &str) -> Vec<String> {
+        match self.options.get(id) {
+            Some(Value::Array(values)) => values.iter().map(value_to_string).collect(),
+            Some(value) => vec![value_to_string(value)],
+            None => self
+                .matches
+                .get_many::<String>(id)
+                .map(|values| values.cloned().collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// remove the options that are set again or conflict with the ones that are set again
+// e.g. --dir on the command line drops file = true from the config file
+fn overwrite<'k>(cmd: &Command, options: &mut Table, ids: impl IntoIterator<Item = &'k str>) {
+    for id in ids {
+        options.remove(id);
+
+        if let Some(arg) = find_arg(cmd, id) {
+            // conflicts are only declared on one of the two arguments
+            for other in cmd.get_arguments() {
+                if is_conflict(cmd, arg, other) || is_conflict(cmd, other, arg) {
+                    options.remove(other.get_id().as_str());
+                }
+            }
+        }
+    }
+}
+
+// every key needs to be a configurable argument with a value of the right type
+fn validate(cmd: &Command, options: Table, section: &str) -> Result<Table, String> {
+    for (key, value) in &options {
+        let arg = find_arg(cmd, key)
+            .filter(|_| !NOT_CONFIGURABLE.contains(&key.as_str()))
+            .ok_or_else(|| format!("Unknown option \'{key}\' in {section}"))?;
+
+        let valid = match arg.get_action() {
+            ArgAction::SetTrue => value.is_bool(),
+            _ => match value {
+                Value::String(_) | Value::Integer(_) => true,
+                Value::Array(values) => {
+                    let multiple = matches!(arg.get_action(), ArgAction::Append)
+                        || arg.get_num_args().is_some_and(|n| n.max_values() > 1);
+                    multiple && values.iter().all(|v| v.is_str() || v.is_integer())
+                }
+                _ => false,
+            },
+        };
+        if !valid {
+            return Err(format!("Invalid value for \'{key}\' in {section}: {value}"));
+        }
+
+        let possible = arg.get_possible_values();
+        if !possible.is_empty()
+            && !possible
+                .iter()
+                .any(|p| p.matches(&value_to_string(value), false))
+        {
+            return Err(format!("Invalid value for \'{key}\' in {section}: {value}"));
+        }
+    }
+
+    Ok(options)
+}
+
+fn is_conflict(cmd: &Command, arg: &Arg, other: &Arg) -> bool {
+    cmd.get_arg_conflicts_with(arg)
+        .iter()
+        .any(|conflict| conflict.get_id() == other.get_id())
+}
+
+fn find_arg<'c>(cmd: &'c Command, id: &str) -> Option<&'c Arg> {
+    cmd.get_arguments().find(|arg| arg.get_id() == id)
+}
+
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_owned(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::temp_dir::TempDir;
+
+    fn parse(args: &[&str]) -> ArgMatches {
+        crate::sf().try_get_matches_from(args).unwrap()
+    }
+
+    fn config_dir(name: &str, config: &str) -> TempDir {
+        let dir = TempDir::new(name);
+        dir.write(CONFIG_FILE, config);
+        dir
+    }
+
+    // like main does it
+    fn load<'a>(matches: &'a ArgMatches, dir: &TempDir) -> Result<Settings<'a>, String> {
+        let profile = matches.get_one::<String>("profile").map(String::as_str);
+        Settings::load(matches, &crate::sf(), dir.path(), profile)
+    }
+
+    const CONFIG: &str = r#"
+[default]
+no-hidden = true
+file = true
+exclude = ["target"]
+threads = 4
+
+[profiles.code]
+extension = ["rs", "py"]
+threads = 8
+sort = "path"
+
+[profiles.dirs]
+dir = true
+"#;
+
+    #[test]
+    fn without_config_file() {
+        let dir = TempDir::new("settings-missing");
+        let matches = parse(&["sf", "a", "."]);
+        let settings = load(&matches, &dir).unwrap();
+        assert!(!settings.flag("no-hidden"));
+        assert_eq!(settings.value("threads").as_deref(), Some("1"));
+        assert!(settings.values("exclude").is_empty());
+
+        let matches = parse(&["sf", "a", ".", "-P", "code"]);
+        let err = load(&matches, &dir).err().unwrap();
+        assert!(err.contains("Profile 'code' not found"), "{}", err);
+    }
+
+    #[test]
+    fn default_table() {
+        let dir = config_dir("settings-default", CONFIG);
+        let matches = parse(&["sf", "a", "."]);
+        let settings = load(&matches, &dir).unwrap();
+        assert!(settings.flag("no-hidden"));
+        assert!(settings.flag("file"));
+        assert_eq!(settings.values("exclude"), ["target"]);
+        // the default value of the argument doesn't override the config file
+        assert_eq!(settings.value("threads").as_deref(), Some("4"));
+        assert_eq!(settings.value("depth").as_deref(), Some("250"));
+        assert!(settings.values("extension").is_empty());
+    }
+
+    #[test]
+    fn profile_over_default() {
+        let dir = config_dir("settings-profile", CONFIG);
+        let matches = parse(&["sf", "a", ".", "--profile", "code"]);
+        let settings = load(&matches, &dir).unwrap();
+        assert!(settings.flag("no-hidden"));
+        assert_eq!(settings.values("extension"), ["rs", "py"]);
+        assert_eq!(settings.value("threads").as_deref(), Some("8"));
+        assert_eq!(settings.value("sort").as_deref(), Some("path"));
+    }
+
+    #[test]
+    fn command_line_over_profile() {
+        let dir = config_dir("settings-cli", CONFIG);
+        let matches = parse(&[
+            "sf", "a", ".", "-P", "code", "-t", "2", "-e", "md", "-E", "out", "build",
+        ]);
+        let settings = load(&matches, &dir).unwrap();
+        assert_eq!(settings.value("threads").as_deref(), Some("2"));
+        assert_eq!(settings.values("extension"), ["md"]);
+        assert_eq!(settings.values("exclude"), ["out", "build"]);
+        assert_eq!(settings.value("sort").as_deref(), Some("path"));
+        // a flag can't be unset on the command line, only overridden by a conflicting one
+        assert!(settings.flag("no-hidden"));
+    }
+
+    #[test]
+    fn conflicts_are_dropped() {
+        let dir = config_dir("settings-conflicts", CONFIG);
+
+        // --dir conflicts with file = true of the config file
+        let matches = parse(&["sf", "a", ".", "-d"]);
+        let settings = load(&matches, &dir).unwrap();
+        assert!(settings.flag("dir"));
+        assert!(!settings.flag("file"));
+        assert!(settings.flag("no-hidden"));
+
+        // and with the extensions of the profile, declared on the other argument
+        let matches = parse(&["sf", "a", ".", "-P", "code", "-d"]);
+        let settings = load(&matches, &dir).unwrap();
+        assert!(!settings.flag("file"));
+        assert!(settings.values("extension").is_empty());
+
+        // a profile drops what conflicts with it from the [default] table
+        let matches = parse(&["sf", "a", ".", "-P", "dirs"]);
+        let settings = load(&matches, &dir).unwrap();
+        assert!(settings.flag("dir"));
+        assert!(!settings.flag("file"));
+    }
+
+    #[test]
+    fn invalid_config() {
+        let cases = [
+            ("[default]\nnope = 1", "Unknown option 'nope' in [default]"),
+            (
+                "[default]\nexec = \"rm\"",
+                "Unknown option 'exec' in [default]",
+            ),
+            (
+                "[profiles.x]\nargs = \"a\"",
+                "Unknown option 'args' in [profiles.x]",
+            ),
+            (
+                "[default]\nno-hidden = \"yes\"",
+                "Invalid value for 'no-hidden'",
+            ),
+            ("[default]\nexclude = [true]", "Invalid value for 'exclude'"),
+            ("[default]\nthreads = [1, 2]", "Invalid value for 'threads'"),
+            ("[default]\nthreads = 1.5", "Invalid value for 'threads'"),
+            ("[default]\nsort = \"nope\"", "Invalid value for 'sort'"),
+            (
+                "[default]\nhidden-mode = \"all\"",
+                "Invalid value for 'hidden-mode'",
+            ),
+            ("default = 1", "Expected a table for [default]"),
+            ("profiles = 1", "Expected a table for [profiles]"),
+            ("[profiles]\nx = 1", "Expected a table for [profiles.x]"),
+            ("[other]", "Unknown key 'other'"),
+            ("[default", "Unable to parse"),
+        ];
+        for (i, (config, expected)) in cases.into_iter().enumerate() {
+            let dir = config_dir(&format!("settings-invalid-{i}"), config);
+            let matches = parse(&["sf", "a", ".", "-P", "x"]);
+            let err = load(&matches, &dir).err().unwrap();
+            assert!(err.contains(expected), "{:?}: {}", config, err);
+        }
+    }
+
+    #[test]
+    fn cli_only() {
+        let matches = parse(&["sf", "a", ".", "-t", "3"]);
+        let settings = Settings::cli_only(&matches);
+        assert!(!settings.flag("no-hidden"));
+        assert_eq!(settings.value("threads").as_deref(), Some("3"));
+        assert_eq!(settings.value("depth").as_deref(), Some("250"));
+    }
+}
@@ -0,0 +1,178 @@
+use std::{error, fmt, str::FromStr};
+
+// units are powers of 1024, the trailing "b" or "ib" is optional
+const UNITS: [(char, u64); 5] = [
+    ('b', 1),
+    ('k', 1 << 10),
+    ('m', 1 << 20),
+    ('g', 1 << 30),
+    ('t', 1 << 40),
+];
+
+/// A condition on the size of a file in bytes
+///
+/// Parsed from `+100M` (at least), `-4k` (at most), `512` (exactly) or `1k..10M` (inclusive range,
+/// either end can be left out). Units are `b`, `k`, `m`, `g` and `t`, case insensitive and powers of 1024.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeFilter {
+    Min(u64),
+    Max(u64),
+    Exact(u64),
+    Range(u64, u64),
+}
+
+impl SizeFilter {
+    /// Whether a file with `size` bytes passes the filter
+    pub fn matches(&self, size: u64) -> bool {
+        match *self {
+            SizeFilter::Min(min) => size >= min,
+            SizeFilter::Max(max) => size <= max,
+            SizeFilter::Exact(exact) => size == exact,
+            SizeFilter::Range(min, max) => (min..=max).contains(&size),
+        }
+    }
+}
+
+impl FromStr for SizeFilter {
+    type Err = ParseSizeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let err = || ParseSizeError(s.to_string());
+
+        if let Some((min, max)) = s.split_once("..") {
+            return match (min.is_empty(), max.is_empty()) {
+                (true, true) => Err(err()),
+                (true, false) => Ok(SizeFilter::Max(parse_bytes(max).ok_or_else(err)?)),
+                (false, true) => Ok(SizeFilter::Min(parse_bytes(min).ok_or_else(err)?)),
+                (false, false) => {
+                    let min = parse_bytes(min).ok_or_else(err)?;
+                    let max = parse_bytes(max).ok_or_else(err)?;
+                    if min > max {
+                        return Err(err());
+                    }
+                    Ok(SizeFilter::Range(min, max))
+                }
+            };
+        }
+
+        if let Some(min) = s.strip_prefix('+') {
+            Ok(SizeFilter::Min(parse_bytes(min).ok_or_else(err)?))
+        } else if let Some(max) = s.strip_prefix('-') {
+            Ok(SizeFilter::Max(parse_bytes(max).ok_or_else(err)?))
+        } else {
+            Ok(SizeFilter::Exact(parse_bytes(s).ok_or_else(err)?))
+        }
+    }
+}
+
+/// A size filter that could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSizeError(String);
+
+impl fmt::Display for ParseSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid size \'{}\', expected e.g. +100M, -4k, 512 or 1k..10M",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseSizeError {}
+
+// "10", "10k", "10kb", "10KiB"
+fn parse_bytes(s: &str) -> Option<u64> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number = number.parse::<u64>().ok()?;
+
+    let unit = unit.to_ascii_lowercase();
+    let factor = match unit.chars().next() {
+        None => 1,
+        Some(c) => {
+            let (_, factor) = UNITS.iter().find(|(u, _)| *u == c)?;
+            // only a suffix "b" or "ib" may follow the unit
+            match &unit[1..] {
+                "" => *factor,
+                "b" | "ib" if c != 'b' => *factor,
+                _ => return None,
+            }
+        }
+    };
+
+    number.checked_mul(factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<SizeFilter, ParseSizeError> {
+        s.parse()
+    }
+
+    #[test]
+    fn bytes() {
+        assert_eq!(parse_bytes("0"), Some(0));
+        assert_eq!(parse_bytes("512"), Some(512));
+        assert_eq!(parse_bytes("512b"), Some(512));
+        assert_eq!(parse_bytes("512B"), Some(512));
+        assert_eq!(parse_bytes("2k"), Some(2 << 10));
+        assert_eq!(parse_bytes("2kb"), Some(2 << 10));
+        assert_eq!(parse_bytes("2KiB"), Some(2 << 10));
+        assert_eq!(parse_bytes("3m"), Some(3 << 20));
+        assert_eq!(parse_bytes("3M"), Some(3 << 20));
+        assert_eq!(parse_bytes("4g"), Some(4 << 30));
+        assert_eq!(parse_bytes("5t"), Some(5 << 40));
+    }
+
+    #[test]
+    fn invalid_bytes() {
+        assert_eq!(parse_bytes(""), None);
+        assert_eq!(parse_bytes("k"), None);
+        assert_eq!(parse_bytes("1x"), None);
+        assert_eq!(parse_bytes("1bb"), None);
+        assert_eq!(parse_bytes("1bib"), None);
+        assert_eq!(parse_bytes("1kbb"), None);
+        assert_eq!(parse_bytes("1.5k"), None);
+        assert_eq!(parse_bytes("-1"), None);
+        // overflows u64
+        assert_eq!(parse_bytes("16777216t"), None);
+    }
+
+    #[test]
+    fn filters() {
+        assert_eq!(parse("+100M"), Ok(SizeFilter::Min(100 << 20)));
+        assert_eq!(parse("-4k"), Ok(SizeFilter::Max(4 << 10)));
+        assert_eq!(parse("512"), Ok(SizeFilter::Exact(512)));
+        assert_eq!(parse(" 512 "), Ok(SizeFilter::Exact(512)));
+        assert_eq!(parse("1k..10M"), Ok(SizeFilter::Range(1 << 10, 10 << 20)));
+        assert_eq!(parse("1k..1024"), Ok(SizeFilter::Range(1024, 1024)));
+        assert_eq!(parse("1k.."), Ok(SizeFilter::Min(1 << 10)));
+        assert_eq!(parse("..10M"), Ok(SizeFilter::Max(10 << 20)));
+    }
+
+    #[test]
+    fn invalid_filters() {
+        for s in [
+            "", "..", "+", "-", "+-1", "10M..1k", "1k..x", "x..1k", "1k...2k",
+        ] {
+            assert_eq!(parse(s), Err(ParseSizeError(s.to_string())), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn matches() {
+        assert!(SizeFilter::Min(10).matches(10));
+        assert!(!SizeFilter::Min(10).matches(9));
+        assert!(SizeFilter::Max(10).matches(10));
+        assert!(!SizeFilter::Max(10).matches(11));
+        assert!(SizeFilter::Exact(10).matches(10));
+        assert!(!SizeFilter::Exact(10).matches(11));
+        assert!(SizeFilter::Range(10, 20).matches(10));
+        assert!(SizeFilter::Range(10, 20).matches(20));
+        assert!(!SizeFilter::Range(10, 20).matches(21));
+    }
+}
@@ -0,0 +1,51 @@
+// directories for the tests, shared by the library and the binary
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// a directory below the system temp dir, removed again when dropped
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    // the name has to be unique among the tests
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("sf-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(fs::canonicalize(path).unwrap())
+    }
+
+    // canonical
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    pub(crate) fn join(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.0.join(path)
+    }
+
+    // empty files, and directories for paths ending in a slash
+    pub(crate) fn create(&self, paths: &[&str]) {
+        for path in paths {
+            match path.strip_suffix('/') {
+                Some(dir) => fs::create_dir_all(self.join(dir)).unwrap(),
+                None => self.write(path, ""),
+            }
+        }
+    }
+
+    pub(crate) fn write(&self, path: &str, content: impl AsRef<[u8]>) {
+        let path = self.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
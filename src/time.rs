@@ -0,0 +1,377 @@
+use std::{
+    error, fmt,
+    fs::{self, Metadata},
+    io,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+// unit names of durations, matched case insensitively
+const UNITS: [(&str, u64); 20] = [
+    ("seconds", 1),
+    ("second", 1),
+    ("sec", 1),
+    ("s", 1),
+    ("minutes", MINUTE),
+    ("minute", MINUTE),
+    ("min", MINUTE),
+    ("m", MINUTE),
+    ("hours", HOUR),
+    ("hour", HOUR),
+    ("h", HOUR),
+    ("days", DAY),
+    ("day", DAY),
+    ("d", DAY),
+    ("weeks", 7 * DAY),
+    ("week", 7 * DAY),
+    ("w", 7 * DAY),
+    ("years", 365 * DAY),
+    ("year", 365 * DAY),
+    ("y", 365 * DAY),
+];
+
+/// Timestamp of an entry a [`TimeFilter`] looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileTime {
+    Modified,
+    Accessed,
+    /// Not available on every platform and filesystem, such entries never match
+    Created,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    After(SystemTime),
+    Before(SystemTime),
+}
+
+/// A condition on a timestamp of an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeFilter {
+    time: FileTime,
+    bound: Bound,
+}
+
+impl TimeFilter {
+    /// The timestamp lies after the given point in time
+    ///
+    /// The point is either a duration back from now, e.g. `2d`, `3h 30min` or `1week`,
+    /// or a UTC date like `2024-01-01` or `2024-01-01 12:00:00`.
+    pub fn within(time: FileTime, point: &str) -> Result<Self, ParseTimeError> {
+        Ok(Self {
+            time,
+            bound: Bound::After(parse_point(point)?),
+        })
+    }
+
+    /// The timestamp lies before the given point in time, see [`TimeFilter::within`]
+    pub fn before(time: FileTime, point: &str) -> Result<Self, ParseTimeError> {
+        Ok(Self {
+            time,
+            bound: Bound::Before(parse_point(point)?),
+        })
+    }
+
+    /// The entry was modified after the given file
+    pub fn newer_than(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            time: FileTime::Modified,
+            bound: Bound::After(fs::metadata(path)?.modified()?),
+        })
+    }
+
+    /// Whether an entry with the given metadata passes the filter
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        let time = match self.time {
+            FileTime::Modified => metadata.modified(),
+            FileTime::Accessed => metadata.accessed(),
+            FileTime::Created => metadata.created(),
+        };
+
+        match (time, self.bound) {
+            (Ok(time), Bound::After(point)) => time > point,
+            (Ok(time), Bound::Before(point)) => time < point,
+            (Err(_), _) => false,
+        }
+    }
+}
+
+/// A duration or date that could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTimeError(String);
+
+impl fmt::Display for ParseTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid time \'{}\', expected a duration like 2d or 3h or a date like 2024-01-01",
+            self.0
+        )
+    }
+}
+
+impl error::Error for ParseTimeError {}
+
+fn parse_point(s: &str) -> Result<SystemTime, ParseTimeError> {
+    let s = s.trim();
+    let err = || ParseTimeError(s.to_string());
+
+    if let Some(secs) = parse_date(s) {
+        return Ok(if secs >= 0 {
+            UNIX_EPOCH + Duration::from_secs(secs as u64)
+        } else {
+            UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+        });
+    }
+
+    let duration = parse_duration(s).ok_or_else(err)?;
+    SystemTime::now().checked_sub(duration).ok_or_else(err)
+}
+
+// "2d", "1h30min", "3 weeks"
+fn parse_duration(s: &str) -> Option<Duration> {
+    let mut rest = s.trim_start();
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut secs: u64 = 0;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number = rest[..split].parse::<u64>().ok()?;
+        rest = rest[split..].trim_start();
+
+        let unit_len = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let unit = rest[..unit_len].to_ascii_lowercase();
+        let (_, factor) = UNITS.iter().find(|(name, _)| *name == unit)?;
+        rest = rest[unit_len..].trim_start();
+
+        secs = secs.checked_add(number.checked_mul(*factor)?)?;
+    }
+
+    Some(Duration::from_secs(secs))
+}
+
+// seconds since the unix epoch of "YYYY-MM-DD", "YYYY-MM-DD HH:MM" or "YYYY-MM-DD HH:MM:SS" in UTC
+fn parse_date(s: &str) -> Option<i64> {
+    let (date, time) = match s.split_once([' ', 'T']) {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next()?.parse::<i64>().ok()?;
+    let month = parts.next()?.parse::<u32>().ok()?;
+    let day = parts.next()?.parse::<u32>().ok()?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    let mut secs = days_from_civil(year, month, day) * DAY as i64;
+    if let Some(time) = time {
+        let mut parts = time.split(':');
+        let hour = parts.next()?.parse::<u32>().ok()?;
+        let minute = parts.next()?.parse::<u32>().ok()?;
+        let second = parts.next().map_or(Some(0), |s| s.parse::<u32>().ok())?;
+        if parts.next().is_some() || hour > 23 || minute > 59 || second > 59 {
+            return None;
+        }
+        secs += (hour as u64 * HOUR + minute as u64 * MINUTE + second as u64) as i64;
+    }
+
+    Some(secs)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// days since 1970-01-01 in the proleptic gregorian calendar
+// http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        let cases = [
+            ("0s", 0),
+            ("2d", 2 * DAY),
+            ("2D", 2 * DAY),
+            ("90 sec", 90),
+            ("1second", 1),
+            ("5 seconds", 5),
+            ("3m", 3 * MINUTE),
+            ("3min", 3 * MINUTE),
+            ("1 minute", MINUTE),
+            ("10minutes", 10 * MINUTE),
+            ("4h", 4 * HOUR),
+            ("1 hour", HOUR),
+            ("12hours", 12 * HOUR),
+            ("1day", DAY),
+            ("3 days", 3 * DAY),
+            ("2w", 14 * DAY),
+            ("1 week", 7 * DAY),
+            ("2weeks", 14 * DAY),
+            ("1y", 365 * DAY),
+            ("1 year", 365 * DAY),
+            ("2years", 730 * DAY),
+            ("1h30min", HOUR + 30 * MINUTE),
+            ("3h 30min", 3 * HOUR + 30 * MINUTE),
+            (" 1d 1s ", DAY + 1),
+        ];
+        for (s, secs) in cases {
+            assert_eq!(
+                parse_duration(s),
+                Some(Duration::from_secs(secs)),
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_durations() {
+        let cases = [
+            "",
+            " ",
+            "2",
+            "d",
+            "2x",
+            "2 dd",
+            "1.5h",
+            "-2d",
+            "2d3",
+            // overflows u64
+            "18446744073709551616s",
+            "18446744073709551615y",
+            "18446744073709551615s 1s",
+        ];
+        for s in cases {
+            assert_eq!(parse_duration(s), None, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn dates() {
+        let cases = [
+            ("1970-01-01", 0),
+            ("1969-12-31", -(DAY as i64)),
+            ("2024-01-01", 19_723 * DAY as i64),
+            ("2024-02-29", 19_782 * DAY as i64),
+            ("2000-02-29", 11_016 * DAY as i64),
+            ("1900-02-28", -25_509 * DAY as i64),
+            ("1970-01-01 00:01", 60),
+            ("1970-01-01 01:00:01", 3_601),
+            ("1970-01-01T23:59:59", 86_399),
+        ];
+        for (s, secs) in cases {
+            assert_eq!(parse_date(s), Some(secs), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn invalid_dates() {
+        let cases = [
+            "2023-02-29",
+            "1900-02-29",
+            "2024-02-30",
+            "2024-04-31",
+            "2024-00-01",
+            "2024-13-01",
+            "2024-01-00",
+            "2024-01",
+            "2024",
+            "2024/01/01",
+            "24-1-1x",
+            "2024-01-01 24:00",
+            "2024-01-01 12:60",
+            "2024-01-01 12:00:60",
+            "2024-01-01 -1:00",
+            "2024-01-01 12:-1",
+            "2024-01-01 12:00:-1",
+            "2024-01-01 12",
+            "2024-01-01 12:00:00:00",
+            "2024-01-01 ",
+        ];
+        for s in cases {
+            assert_eq!(parse_date(s), None, "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn leap_years() {
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+    }
+
+    #[test]
+    fn civil_days() {
+        let cases = [
+            (1970, 1, 1, 0),
+            (1970, 1, 2, 1),
+            (1969, 12, 31, -1),
+            (2000, 1, 1, 10_957),
+            (2000, 3, 1, 11_017),
+            (2023, 3, 1, 19_417),
+            (2024, 3, 1, 19_783),
+            (1900, 3, 1, -25_508),
+            (1600, 1, 1, -135_140),
+        ];
+        for (year, month, day, days) in cases {
+            assert_eq!(
+                days_from_civil(year, month, day),
+                days,
+                "{}-{}-{}",
+                year,
+                month,
+                day
+            );
+        }
+    }
+
+    #[test]
+    fn points() {
+        assert!(parse_point("2d").is_ok());
+        assert_eq!(
+            parse_point(" 1970-01-02 "),
+            Ok(UNIX_EPOCH + Duration::from_secs(DAY))
+        );
+        assert_eq!(
+            parse_point("1969-12-31"),
+            Ok(UNIX_EPOCH - Duration::from_secs(DAY))
+        );
+        assert_eq!(
+            parse_point("yesterday"),
+            Err(ParseTimeError("yesterday".to_string()))
+        );
+        assert!(parse_point("100000000000000y").is_err());
+    }
+}
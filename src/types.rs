@@ -0,0 +1,142 @@
+use std::{error, fmt, fs, str::FromStr};
+
+use crate::entry::Entry;
+
+#[cfg(unix)]
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+// extensions windows runs without asking for a program
+#[cfg(windows)]
+const EXECUTABLE_EXTENSIONS: [&str; 5] = ["exe", "com", "bat", "cmd", "ps1"];
+
+/// Type of entry to search for with [`SearchBuilder::types`](crate::SearchBuilder::types)
+///
+/// Parsed from its name, e.g. `executable` or `broken-symlink`, or a single letter alias.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryType {
+    File,
+    Dir,
+    Symlink,
+    /// A file with an execute permission, or an executable extension on windows
+    Executable,
+    /// An empty file or a directory without entries
+    Empty,
+    Socket,
+    Fifo,
+    BlockDevice,
+    CharDevice,
+    /// A symlink whose target doesn't exist
+    BrokenSymlink,
+}
+
+impl EntryType {
+    /// Names and aliases as accepted by [`str::parse`]
+    pub const NAMES: [(&'static str, &'static str); 10] = [
+        ("file", "f"),
+        ("dir", "d"),
+        ("symlink", "l"),
+        ("executable", "x"),
+        ("empty", "e"),
+        ("socket", "s"),
+        ("fifo", "p"),
+        ("block-device", "b"),
+        ("char-device", "c"),
+        ("broken-symlink", "B"),
+    ];
+
+    const ALL: [EntryType; 10] = [
+        EntryType::File,
+        EntryType::Dir,
+        EntryType::Symlink,
+        EntryType::Executable,
+        EntryType::Empty,
+        EntryType::Socket,
+        EntryType::Fifo,
+        EntryType::BlockDevice,
+        EntryType::CharDevice,
+        EntryType::BrokenSymlink,
+    ];
+
+    // symlinks are not followed, entries that can't be read never match
+    pub(crate) fn matches<E: Entry>(&self, entry: &E) -> bool {
+        let file_type = entry.file_type();
+        match self {
+            EntryType::File => file_type.is_file(),
+            EntryType::Dir => file_type.is_dir(),
+            EntryType::Symlink => file_type.is_symlink(),
+            EntryType::Executable => file_type.is_file() && is_executable(entry),
+            EntryType::Empty => {
+                if file_type.is_file() {
+                    entry.metadata().is_ok_and(|metadata| metadata.len() == 0)
+                } else if file_type.is_dir() {
+                    fs::read_dir(entry.path()).is_ok_and(|mut dir| dir.next().is_none())
+                } else {
+                    false
+                }
+            }
+            EntryType::BrokenSymlink => {
+                file_type.is_symlink() && fs::metadata(entry.path()).is_err()
+            }
+            #[cfg(unix)]
+            EntryType::Socket => file_type.is_socket(),
+            #[cfg(unix)]
+            EntryType::Fifo => file_type.is_fifo(),
+            #[cfg(unix)]
+            EntryType::BlockDevice => file_type.is_block_device(),
+            #[cfg(unix)]
+            EntryType::CharDevice => file_type.is_char_device(),
+            #[cfg(not(unix))]
+            EntryType::Socket
+            | EntryType::Fifo
+            | EntryType::BlockDevice
+            | EntryType::CharDevice => false,
+        }
+    }
+}
+
+impl FromStr for EntryType {
+    type Err = ParseTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::NAMES
+            .iter()
+            .position(|(name, alias)| s == *name || s == *alias)
+            .map(|i| Self::ALL[i])
+            .ok_or_else(|| ParseTypeError(s.to_string()))
+    }
+}
+
+/// An entry type that could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTypeError(String);
+
+impl fmt::Display for ParseTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = EntryType::NAMES.map(|(name, _)| name).join(", ");
+        write!(f, "Invalid type \'{}\', expected one of {names}", self.0)
+    }
+}
+
+impl error::Error for ParseTypeError {}
+
+#[cfg(unix)]
+fn is_executable<E: Entry>(entry: &E) -> bool {
+    entry
+        .metadata()
+        .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(windows)]
+fn is_executable<E: Entry>(entry: &E) -> bool {
+    entry.path().extension().is_some_and(|extension| {
+        let extension = extension.to_string_lossy();
+        EXECUTABLE_EXTENSIONS
+            .iter()
+            .any(|it| extension.eq_ignore_ascii_case(it))
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_executable<E: Entry>(_entry: &E) -> bool {
+    false
+}
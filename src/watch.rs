@@ -0,0 +1,96 @@
+use log::warn;
+use notify::{
+    event::{ModifyKind, RenameMode},
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+// an entry below a watched path that appeared or disappeared
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    // created or renamed to
+    Created(PathBuf),
+    // deleted or renamed from
+    Removed(PathBuf),
+}
+
+// reports the changes below the search paths, via inotify on linux
+pub struct Watch {
+    // stops watching once dropped
+    _watcher: RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<Event>>,
+    pending: VecDeque<Change>,
+}
+
+impl Watch {
+    pub fn new(paths: &[&Path]) -> notify::Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        for path in paths {
+            watcher.watch(path, RecursiveMode::Recursive)?;
+        }
+
+        Ok(Self {
+            _watcher: watcher,
+            events,
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+impl Iterator for Watch {
+    type Item = notify::Result<Change>;
+
+    // blocks until the next change
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(change) = self.pending.pop_front() {
+                return Some(Ok(change));
+            }
+
+            match self.events.recv().ok()? {
+                Ok(event) => {
+                    if event.need_rescan() {
+                        warn!("Too many changes at once, some of them were missed");
+                    }
+                    self.pending.extend(changes(event));
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+// modifications of the content or the metadata of an entry are no changes
+fn changes(event: Event) -> Vec<Change> {
+    let mut paths = event.paths;
+    match event.kind {
+        EventKind::Create(_) => paths.into_iter().map(Change::Created).collect(),
+        EventKind::Remove(_) => paths.into_iter().map(Change::Removed).collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+            paths.into_iter().map(Change::Removed).collect()
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+            paths.into_iter().map(Change::Created).collect()
+        }
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+            let to = paths.pop().expect("two paths");
+            let from = paths.pop().expect("two paths");
+            vec![Change::Removed(from), Change::Created(to)]
+        }
+        // backends that can't tell which side of a rename the path is on
+        EventKind::Modify(ModifyKind::Name(_)) => paths
+            .into_iter()
+            .map(|path| match path.symlink_metadata() {
+                Ok(_) => Change::Created(path),
+                Err(_) => Change::Removed(path),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}